@@ -0,0 +1,889 @@
+use cp::checker::Checker;
+use cp::compare;
+use cp::config::Config;
+use cp::diff::{self, Style};
+use cp::exec::{self, Execution, Program};
+use cp::history::History;
+use cp::interactor::Interactor;
+use cp::lang::{self, BuildOptions};
+use cp::output::{self, OutputDir};
+use cp::report::{self, RunReport, TestResult};
+use cp::stress::{Failure, Stress};
+use cp::testcase::{self, Glob, Selection, TestCase};
+use cp::threadpool::{InOrder, ThreadPool};
+use cp::verdict::{Judge, Judgement, Summary, Verdict};
+use cp::watch::Watcher;
+use std::{
+    collections::HashMap,
+    env,
+    error::Error,
+    ffi::OsString,
+    fs,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// How often `--watch` looks for changes.
+static WATCH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// What the command line asked for.
+#[derive(Clone)]
+struct Args {
+    file_name: PathBuf,
+    /// `stress <generator> <brute> <solution>`
+    stress: Option<(PathBuf, PathBuf)>,
+    flags: Vec<bool>,
+    n_threads: usize,
+    judge: Judge,
+    checker: Option<PathBuf>,
+    interactor: Option<PathBuf>,
+    transcripts: Option<PathBuf>,
+    first_seed: u64,
+    seeds: Option<u64>,
+    /// Stress: shrink the failing test.
+    shrink: bool,
+    /// Print the tests as they finish instead of in order.
+    completion_order: bool,
+    selection: Selection,
+    /// Stop at the first failing test.
+    fail_fast: bool,
+    /// Only run the tests that failed last time.
+    failed: bool,
+    /// Show wrong outputs next to the expected ones.
+    side_by_side: bool,
+    /// Run again whenever a source or a test changes.
+    watch: bool,
+    /// Show outputs as hexdumps.
+    hexdump: bool,
+    /// Replace an output folder not created by us.
+    force: bool,
+    /// Write the outputs in a new folder every run.
+    keep_runs: bool,
+    report: Option<(report::Format, PathBuf)>,
+}
+
+fn main() {
+    // Returned from `main`, errors would be shown with `Debug`
+    if let Err(e) = start() {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
+
+fn start() -> Result<()> {
+    let current_dir = env::current_dir().expect("Can't get current directory");
+    let config = Config::find(&current_dir)?;
+    let args = process_args(&config);
+    if args.watch {
+        watch(args, &config, &current_dir)
+    } else {
+        run(args, &config, &current_dir)
+    }
+}
+
+/// Runs again whenever the sources or the tests change, cancelling the run
+/// still going if there is one.
+fn watch(args: Args, config: &Config, current_dir: &Path) -> Result<()> {
+    if args.stress.is_some() {
+        panic!("--watch only works with tests, not with stress. Try to use -h flag");
+    }
+    loop {
+        // Again every time, the sources may include new files
+        let mut paths = Vec::new();
+        let sources = [
+            Some(&args.file_name),
+            args.checker.as_ref(),
+            args.interactor.as_ref(),
+        ];
+        for source in sources.into_iter().flatten() {
+            for path in lang::sources(source, &config.languages) {
+                paths.push((current_dir.join(path), true));
+            }
+        }
+        // Only the tests of the current directory, not whatever else is
+        // written there, and the test folders, even new ones
+        let naming = config.naming.clone();
+        let mut watcher = Watcher::new(paths).filtered(current_dir.to_path_buf(), move |path| {
+            testcase::is_test_path(path, &naming)
+        });
+        let cancel = Arc::new(AtomicBool::new(false));
+        let changed = thread::spawn({
+            let cancel = Arc::clone(&cancel);
+            move || {
+                watcher.wait(WATCH_INTERVAL);
+                cancel.store(true, Ordering::Relaxed);
+            }
+        });
+        // Clear the screen
+        print!("\x1b[2J\x1b[H");
+        let mut args = args.clone();
+        args.judge.limits.cancel = Some(Arc::clone(&cancel));
+        if let Err(e) = run(args, config, current_dir) {
+            eprintln!("Error: {e}");
+        }
+        if !cancel.load(Ordering::Relaxed) {
+            eprintln!("Waiting for changes...");
+        }
+        changed.join().unwrap();
+    }
+}
+
+/// Builds everything and runs the tests, or stresses the solution.
+fn run(mut args: Args, config: &Config, current_dir: &Path) -> Result<()> {
+    let options = BuildOptions {
+        release: args.flags[0],
+        warnings: args.flags[2],
+    };
+    let solution = lang::build(&args.file_name, &options, &config.languages)?;
+    args.judge.limits.enforce_memory = !solution.sanitized;
+    // Helper programs are always built in release mode
+    let tool_options = BuildOptions {
+        release: true,
+        warnings: false,
+    };
+    if let Some(source) = &args.checker {
+        let checker = lang::build(source, &tool_options, &config.languages)?;
+        args.judge.checker = Some(Checker::new(checker.program));
+    }
+    if let Some(source) = &args.interactor {
+        let interactor = lang::build(source, &tool_options, &config.languages)?;
+        let transcripts = args.transcripts.clone();
+        args.judge.interactor = Some(Interactor::new(interactor.program, transcripts));
+    }
+    if let Some((generator, brute)) = &args.stress {
+        let generator = lang::build(generator, &tool_options, &config.languages)?;
+        let brute = lang::build(brute, &tool_options, &config.languages)?;
+        let stress = Stress {
+            generator: generator.program,
+            brute: brute.program,
+            solution: solution.program.clone(),
+            judge: args.judge.clone(),
+        };
+        run_stress(stress, current_dir, &config.naming.folders, &args)?;
+    } else {
+        let tests = testcase::discover(current_dir, &config.naming)?;
+        // Named among all the tests, so that selecting some doesn't rename them
+        let mut output_names: HashMap<_, _> = tests
+            .iter()
+            .map(|test| test.input.clone())
+            .zip(output::names(&tests, current_dir))
+            .collect();
+        let mut inputs = args.selection.apply(tests);
+        if let Some((n, seed)) = args.selection.sample {
+            eprintln!(
+                "Sampled {} of the tests with --sample {n}:{seed}",
+                inputs.len()
+            );
+        }
+        let mut history = History::load(current_dir, &args.file_name)?;
+        if args.failed {
+            inputs.retain(|test| history.failed(&test.input));
+            if inputs.is_empty() {
+                eprintln!("No test failed last time");
+            }
+        }
+        let output_dir = current_dir.join(&config.output_dir);
+        if args.flags[1] {
+            let folders = testcase::folders(current_dir, &config.naming.folders)?;
+            output::check(&output_dir, current_dir, &folders)?;
+        }
+        let output_dir = match (args.flags[1], args.keep_runs) {
+            (false, _) => None,
+            (true, false) => Some(OutputDir::create(&output_dir, args.force)?),
+            (true, true) => {
+                let run = OutputDir::create_run(&output_dir, args.force)?;
+                eprintln!("Writing the outputs in {}", run.path().display());
+                Some(run)
+            }
+        };
+        let output = output_dir.map(|dir| {
+            let names = inputs
+                .iter()
+                .map(|test| output_names.remove(&test.input).unwrap_or_default())
+                .collect();
+            (dir, names)
+        });
+        let mut tests = run_test_cases(
+            solution.program.clone(),
+            inputs,
+            output,
+            args.judge,
+            RunOptions {
+                n_threads: args.n_threads,
+                completion_order: args.completion_order,
+                fail_fast: args.fail_fast,
+                side_by_side: args.side_by_side,
+                hexdump: args.hexdump,
+            },
+            &mut history,
+        )?;
+        history.save()?;
+        if let Some((format, path)) = &args.report {
+            for test in &mut tests {
+                if let Ok(input) = test.input.strip_prefix(current_dir) {
+                    test.input = input.to_path_buf();
+                }
+            }
+            let report = RunReport {
+                build: solution.info.clone(),
+                tests,
+            };
+            report.write(*format, path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the command line, on top of the settings of `config`.
+fn process_args(config: &Config) -> Args {
+    let mut files = Vec::new();
+    let mut flags = vec![false; 3];
+    let mut n_threads = match config.parallel {
+        Some(0) => std::thread::available_parallelism().unwrap().get(),
+        Some(n_threads) => n_threads,
+        None => 1,
+    };
+    let mut judge = Judge::default();
+    judge.limits.time = config.time_limit;
+    judge.limits.memory = config.memory_limit;
+    judge.compare = config.compare;
+    let mut checker = None;
+    let mut interactor = None;
+    let mut transcripts = None;
+    let mut first_seed = 1;
+    let mut seeds = None;
+    let mut shrink = false;
+    let mut completion_order = false;
+    let mut selection = Selection::default();
+    let mut fail_fast = false;
+    let mut failed = false;
+    let mut side_by_side = false;
+    let mut watch = false;
+    let mut hexdump = false;
+    let mut force = false;
+    let mut keep_runs = config.keep_runs;
+    let mut report = None;
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        // Options are always UTF-8, files may be anything
+        let arg = match arg.into_string() {
+            Ok(arg) => arg,
+            Err(file) => {
+                files.push(PathBuf::from(file));
+                continue;
+            }
+        };
+        let bytes = arg.as_bytes();
+        if bytes[0] == b'-' {
+            if bytes[1] == b'-' {
+                match arg.as_str() {
+                    "--release" => flags[0] = true,
+                    "--output-file" => flags[1] = true,
+                    "--parallel" => {
+                        let start = "--parallel".len();
+                        let end = change_n_threads(&mut n_threads, bytes, start);
+                        if end < arg.len() {
+                            panic!("Invalid arguments {arg}. Try to use -h flag");
+                        }
+                    }
+                    "--warning" => flags[2] = true,
+                    "--time-limit" => {
+                        let ms = next_value(&mut args, &arg);
+                        judge.limits.time = Some(Duration::from_millis(ms));
+                    }
+                    "--memory-limit" => {
+                        let mb: u64 = next_value(&mut args, &arg);
+                        judge.limits.memory = Some(mb * 1024 * 1024);
+                    }
+                    "--checker" => checker = Some(next_path(&mut args, &arg)),
+                    "--interactor" => interactor = Some(next_path(&mut args, &arg)),
+                    "--transcript" => transcripts = Some(next_path(&mut args, &arg)),
+                    "--compare" => judge.compare = next_value(&mut args, &arg),
+                    "--seed" => first_seed = next_value(&mut args, &arg),
+                    "--seeds" => seeds = Some(next_value(&mut args, &arg)),
+                    "--shrink" => shrink = true,
+                    "--completion-order" => completion_order = true,
+                    "--only" => selection.only.push(next_value(&mut args, &arg)),
+                    "--skip" => selection.skip.push(next_value(&mut args, &arg)),
+                    "--fail-fast" => fail_fast = true,
+                    "--failed" => failed = true,
+                    "--side-by-side" => side_by_side = true,
+                    "--watch" => watch = true,
+                    "--hexdump" => hexdump = true,
+                    "--force" => force = true,
+                    "--keep-runs" => keep_runs = true,
+                    "--report" => {
+                        let format = next_value(&mut args, &arg);
+                        report = Some((format, next_path(&mut args, &arg)));
+                    }
+                    "--live" => judge.limits.live = true,
+                    "--sample" => {
+                        selection.sample = Some(sample(&next_value::<String>(&mut args, &arg)))
+                    }
+                    "--help" => {
+                        print_help();
+                        std::process::exit(0);
+                    }
+                    _ => panic!("Invalid arguments {arg}. Try to use -h flag"),
+                }
+            } else {
+                let mut i = 1;
+                while i < bytes.len() {
+                    match bytes[i] {
+                        b'r' => flags[0] = true,
+                        b'o' => flags[1] = true,
+                        b'p' => {
+                            i = change_n_threads(&mut n_threads, bytes, i + 1) - 1;
+                        }
+                        b'w' => flags[2] = true,
+                        b'h' => {
+                            print_help();
+                            std::process::exit(0);
+                        }
+                        _ => panic!("Invalid arguments {arg}. Try to use -h flag"),
+                    }
+                    i += 1;
+                }
+            }
+        } else {
+            files.push(PathBuf::from(arg));
+        }
+    }
+    if judge.limits.live {
+        // The outputs of tests running together would be mixed up
+        n_threads = 1;
+    }
+    let (file_name, stress) = match files.as_slice() {
+        [] => panic!("No input file specified. Try to use -h flag"),
+        [file_name] => (file_name.clone(), None),
+        [mode, generator, brute, file_name] if mode.as_os_str() == "stress" => {
+            (file_name.clone(), Some((generator.clone(), brute.clone())))
+        }
+        _ => panic!("Invalid arguments. Try to use -h flag"),
+    };
+    if stress.is_some() && report.is_some() {
+        panic!("--report only works with tests, not with stress. Try to use -h flag");
+    }
+    Args {
+        file_name,
+        stress,
+        flags,
+        n_threads,
+        judge,
+        checker,
+        interactor,
+        transcripts,
+        first_seed,
+        seeds,
+        shrink,
+        completion_order,
+        selection,
+        fail_fast,
+        failed,
+        side_by_side,
+        watch,
+        hexdump,
+        force,
+        keep_runs,
+        report,
+    }
+}
+
+/// `n` or `n:seed`, with a seed from the clock if there is none.
+fn sample(value: &str) -> (usize, u64) {
+    let (n, seed) = match value.split_once(':') {
+        Some((n, seed)) => (n, seed.parse().ok()),
+        None => (value, Some(random_seed())),
+    };
+    match (n.parse(), seed) {
+        (Ok(n), Some(seed)) => (n, seed),
+        _ => panic!("Invalid value for --sample. Try to use -h flag"),
+    }
+}
+
+fn random_seed() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    now.as_secs() ^ now.subsec_nanos() as u64
+}
+
+fn next_value<T: str::FromStr>(args: &mut impl Iterator<Item = OsString>, arg: &str) -> T {
+    match args
+        .next()
+        .as_ref()
+        .and_then(|value| value.to_str()?.parse().ok())
+    {
+        Some(value) => value,
+        None => panic!("Invalid value for {arg}. Try to use -h flag"),
+    }
+}
+
+fn next_path(args: &mut impl Iterator<Item = OsString>, arg: &str) -> PathBuf {
+    match args.next() {
+        Some(path) => PathBuf::from(path),
+        None => panic!("Invalid value for {arg}. Try to use -h flag"),
+    }
+}
+
+fn change_n_threads(n_threads: &mut usize, bytes: &[u8], start: usize) -> usize {
+    let mut j = start;
+    while j < bytes.len() && bytes[j] >= b'0' && bytes[j] <= b'9' {
+        j += 1;
+    }
+    let all = std::thread::available_parallelism().unwrap().get();
+    // No number or 0 is all of them, like `parallel = 0` in the settings
+    *n_threads = match str::from_utf8(&bytes[start..j]).unwrap().parse::<usize>() {
+        Ok(0) | Err(_) => all,
+        Ok(n) => n.min(all),
+    };
+    j
+}
+
+fn print_help() {
+    println!("Usage: cp-io [file_name] [option]");
+    println!("       cp-io stress [generator] [brute] [file_name] [option]");
+    println!();
+    println!("`file_name` is a cargo binary (`sol`, `sol.rs` or `src/bin/sol.rs`), a C++ (`.cpp`, `.cc`),");
+    println!("C (`.c`) or Python (`.py`) source. Other files are run as executables.");
+    println!(
+        "The binaries of C and C++ sources are cached in `target/cp/build/` until the source, the"
+    );
+    println!("headers next to it that it includes with quotes or the flags change. Remove the");
+    println!("folder to rebuild after changing other headers.");
+    println!();
+    println!("Options:");
+    println!("  -r    --release      Build in release mode (-Ofast for C and C++)");
+    println!("  -o    --output-file  Write the outputs in the output folder (`output/`), removing");
+    println!("                       the ones of the previous run. `input/07.in` gets `07.out`,");
+    println!("                       `07.err` with its stderr and `07.meta` with how it ran");
+    println!("  --keep-runs          With -o, write them in a new `output/<time>/` folder instead");
+    println!("  --report <format> <file>");
+    println!("                       Write the verdict, times, memory and exit status of every");
+    println!("                       test and how the solution was built in `file`, as `json`");
+    println!("                       or `junit` XML");
+    println!("  --force              With -o, replace an output folder that cp-io did not create");
+    println!("  -p[n] --parallel[n]  Run test cases in parallel");
+    println!("                       With `n` it spawns `n` threads, at most the available ones,");
+    println!("                       without it or with 0 all the available ones");
+    println!("  -w    --warning      Show warning messages");
+    println!("  --completion-order   Print the tests as they finish, not in order");
+    println!("  --time-limit <ms>    Kill the tests running longer than `ms` milliseconds (TLE)");
+    println!("  --memory-limit <MB>  Limit the memory of the tests to `MB` megabytes (MLE)");
+    println!("                       For C and C++ without -r it is only checked against the peak");
+    println!("                       memory usage");
+    println!(
+        "  --checker <file>     Judge the outputs with a testlib checker, built in release mode"
+    );
+    println!("                       It can also be the path of an executable");
+    println!("  --interactor <file>  Run interactive problems against a testlib interactor, built");
+    println!("                       like the checker. It gets the test as input");
+    println!(
+        "  --transcript <dir>   Write the exchange with the interactor of every test in `dir`"
+    );
+    println!("  --compare <mode>     Compare the outputs with the expected answers by");
+    println!(
+        "                       `tokens` (default), `exact` bytes, `float[:eps]` (default 1e-6),"
+    );
+    println!("                       `icase` case-insensitive tokens or unordered `lines`");
+    println!("  --seed <n>           Stress: start from seed `n` (default 1)");
+    println!("  --seeds <n>          Stress: stop after `n` seeds (default never)");
+    println!("  --shrink             Stress: also save a smaller test on which the solution fails");
+    println!(
+        "  --only <test>        Only run the tests whose path (`tests/a/1.in`) or file name is"
+    );
+    println!("                       like the pattern `test` (`*` anything, `?` a character), or");
+    println!("                       at the positions `i`, `i-j` or `i-`");
+    println!("                       Can be repeated");
+    println!("  --skip <test>        Don't run the tests selected like --only. Can be repeated");
+    println!(
+        "  --sample <n>[:seed]  Run `n` tests picked at random, printing the seed to pick the"
+    );
+    println!("                       same ones again");
+    println!("  --fail-fast          Stop at the first failing test");
+    println!("  --watch              Build and run again whenever the sources, the checker or the");
+    println!("                       tests change");
+    println!("  --live               Show the output of the tests as it comes, running them one");
+    println!("                       at a time");
+    println!("  --side-by-side       Show wrong answers next to the expected ones instead of one");
+    println!("                       after the other");
+    println!("  --hexdump            Show outputs and tests as hexdumps, by default the bytes");
+    println!("                       that are not UTF-8 are replaced");
+    println!(
+        "  --failed             Only run the tests that failed the last time they were run with"
+    );
+    println!("                       the same `file_name`, as saved in `target/cp/`");
+    println!("  -h    --help         Print this help message");
+    println!("Note: you can also aggregate options like `-rp4o`");
+    println!();
+    println!("Default settings are read from `cp.toml`, or from `[package.metadata.cp]` in `Cargo.toml`,");
+    println!("in the current directory or in its parents. The options above override them.");
+    println!();
+    println!(
+        "Tests are looked for in the current directory and, recursively, in the folders named"
+    );
+    println!("like `input*`, `test*` or `sample*`, and run in natural order (`9` before `10`).");
+    println!("The inputs are named like `input*.txt`, `*.in` or `{{n}}` (a number), and the expected answer");
+    println!("of `input3.txt` is `output3.txt`, the one of `3.in` is `3.ans` or `3.out`, the one of `3` is `3.a`.");
+    println!(
+        "Other names and folders can be set with `[[tests]]` and `test-folders` in `cp.toml`."
+    );
+    println!();
+    println!(
+        "The stress mode runs `generator <seed>` with increasing seeds and compares the outputs"
+    );
+    println!(
+        "of `brute` and `file_name` on its tests. The generator and the brute force are built"
+    );
+    println!("like the checker. The first failing test is saved in the input folder.");
+}
+
+fn run_stress(
+    stress: Stress,
+    current_dir: &Path,
+    test_folders: &[Glob],
+    args: &Args,
+) -> Result<()> {
+    let stress = Arc::new(stress);
+    let failure = stress.run(args.first_seed, args.seeds, args.n_threads, |tried| {
+        eprint!("\r{tried} seeds passed");
+    })?;
+    eprintln!();
+    let failure = match failure {
+        Some(failure) => failure,
+        None => return Ok(()),
+    };
+    let folder = input_folder(current_dir, test_folders)?;
+    let input = failure.save(&folder)?;
+    let name = format!("seed {}", failure.seed);
+    print_failure(&failure, &name, &input, args.hexdump)?;
+    if args.shrink {
+        eprintln!("Shrinking...");
+        let shrunk = stress.shrink(&failure)?;
+        let input = shrunk.save_shrunk(&folder)?;
+        print_failure(&shrunk, "shrunk", &input, args.hexdump)?;
+    }
+    Ok(())
+}
+
+fn print_failure(failure: &Failure, name: &str, saved_as: &Path, hexdump: bool) -> Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    print_cool(&format!("{name}: {}", failure.judgement), &mut stdout)?;
+    show(&failure.input, hexdump, &mut stdout)?;
+    print_cool("(expected)", &mut stdout)?;
+    show(&failure.expected, hexdump, &mut stdout)?;
+    print_cool("(found)", &mut stdout)?;
+    show(&failure.found, hexdump, &mut stdout)?;
+    print_cool(&format!("saved as {}", saved_as.display()), &mut stdout)?;
+    Ok(())
+}
+
+/// The first test folder, or a new `input` one.
+fn input_folder(current_dir: &Path, test_folders: &[Glob]) -> Result<PathBuf> {
+    let folders = testcase::folders(current_dir, test_folders)?;
+    Ok(folders
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| current_dir.join("input")))
+}
+
+/// How `run_test_cases` goes through the tests.
+struct RunOptions {
+    n_threads: usize,
+    /// Print the tests as they finish instead of in order.
+    completion_order: bool,
+    /// Stop at the first failing test.
+    fail_fast: bool,
+    side_by_side: bool,
+    hexdump: bool,
+}
+
+/// How `print_result` shows a test.
+struct Report {
+    compare: compare::Mode,
+    style: Style,
+    /// The output was already shown while the test ran.
+    live: bool,
+    hexdump: bool,
+}
+
+fn run_test_cases(
+    program: Program,
+    inputs: Vec<TestCase>,
+    // With the name of the output of every test
+    mut output: Option<(OutputDir, Vec<PathBuf>)>,
+    judge: Judge,
+    options: RunOptions,
+    history: &mut History,
+) -> Result<Vec<TestResult>> {
+    let total = inputs.len();
+    let live = judge.limits.live;
+    let report = Report {
+        compare: judge.compare,
+        style: Style {
+            side_by_side: options.side_by_side,
+            color: io::stdout().is_terminal(),
+            width: terminal_width(),
+            context: 2,
+        },
+        live,
+        hexdump: options.hexdump,
+    };
+    // Set on the first failure with --fail-fast, the tests not started yet are
+    // not run anymore
+    let stopped = Arc::new(AtomicBool::new(false));
+    // Set by --watch when the run is replaced by a new one
+    let cancel = judge.limits.cancel.clone();
+    let cancelled = move || {
+        cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    };
+    let run_test = {
+        let stopped = Arc::clone(&stopped);
+        let cancelled = cancelled.clone();
+        move |test: TestCase| {
+            if stopped.load(Ordering::Relaxed) || cancelled() {
+                return None;
+            }
+            if live {
+                // Tests run one at a time, their output follows
+                let _ = print_cool(&test.name, &mut io::stdout().lock());
+            }
+            let result = judge.run(&program, &test);
+            Some((test, result))
+        }
+    };
+    // Kept until the end, dropping it waits for the tests still queued
+    let pool;
+    let finished: Box<dyn Iterator<Item = _>> = if live {
+        // On this thread, so that a test only starts once the previous one is
+        // printed
+        Box::new(inputs.into_iter().map(run_test).enumerate())
+    } else {
+        pool = ThreadPool::new(options.n_threads);
+        Box::new(pool.map(inputs, run_test).into_iter())
+    };
+    let mut summary = Summary::default();
+    let mut results = Vec::new();
+    let mut in_order = InOrder::default();
+    let mut progress = Progress::default();
+    let mut run = 0;
+    'tests: for (done, (i, result)) in finished.enumerate() {
+        let ready = if options.completion_order {
+            vec![(i, result)]
+        } else {
+            in_order.push(i, (i, result))
+        };
+        progress.clear();
+        if cancelled() {
+            return Ok(results);
+        }
+        for (i, result) in ready {
+            let Some((test, result)) = result else {
+                continue;
+            };
+            let (execution, judgement) = result?;
+            let output_file = match &mut output {
+                Some((dir, names)) => {
+                    let name = &names[i];
+                    dir.write(name, &execution.stdout)?;
+                    dir.write(&name.with_extension("err"), &execution.stderr)?;
+                    let meta = meta(&execution, &judgement);
+                    dir.write(&name.with_extension("meta"), meta.as_bytes())?;
+                    Some(name.clone())
+                }
+                None => None,
+            };
+            let name = test.name.clone();
+            results.push(TestResult::new(
+                name.clone(),
+                &test.input,
+                &execution,
+                &judgement,
+            ));
+            print_result(&test, execution, &judgement, output_file, &report)?;
+            summary.add(name, judgement.verdict);
+            history.record(&test.input, judgement.verdict);
+            run += 1;
+            if options.fail_fast && judgement.verdict.is_failure() {
+                stopped.store(true, Ordering::Relaxed);
+                break 'tests;
+            }
+        }
+        if !live {
+            progress.show(format!("{}/{} tests done", done + 1, total));
+        }
+    }
+    progress.clear();
+    let mut stdout = std::io::stdout().lock();
+    let mut headline = summary.headline();
+    if run < total {
+        headline.push_str(&format!(", stopped with {} not run", total - run));
+    }
+    print_cool(&headline, &mut stdout)?;
+    for (name, verdict) in summary.failures() {
+        writeln!(stdout, "{verdict}: {name}")?;
+    }
+    Ok(results)
+}
+
+/// A line on stderr telling how many tests are done, rewritten in place. It is
+/// only shown on a terminal.
+#[derive(Default)]
+struct Progress {
+    shown: usize,
+}
+
+impl Progress {
+    fn show(&mut self, line: String) {
+        if !io::stderr().is_terminal() {
+            return;
+        }
+        eprint!("\r{line}");
+        self.shown = line.len();
+    }
+
+    fn clear(&mut self) {
+        if self.shown == 0 {
+            return;
+        }
+        eprint!("\r{:1$}\r", "", self.shown);
+        self.shown = 0;
+    }
+}
+
+/// The `.meta` file written next to an output: how the test ran, one
+/// `key: value` per line.
+fn meta(execution: &Execution, judgement: &Judgement) -> String {
+    let mut meta = format!("verdict: {}\n", judgement.verdict);
+    if let Some(points) = judgement.points {
+        meta.push_str(&format!("points: {points}\n"));
+    }
+    if let Some(comment) = &judgement.comment {
+        meta.push_str(&format!("comment: {comment}\n"));
+    }
+    meta.push_str(&format!("wall: {}\n", exec::millis(execution.elapsed)));
+    if let Some(usage) = execution.usage {
+        meta.push_str(&format!("cpu: {}\n", exec::millis(usage.cpu())));
+        meta.push_str(&format!("memory: {} bytes\n", usage.max_rss));
+    }
+    meta.push_str(&format!("status: {}\n", execution.exit_status()));
+    meta
+}
+
+fn print_result(
+    test: &TestCase,
+    output: Execution,
+    judgement: &Judgement,
+    output_file: Option<PathBuf>,
+    report: &Report,
+) -> Result<()> {
+    let test_name = &test.name;
+    let stats = match output.usage {
+        Some(usage) => format!("wall {} {}", exec::millis(output.elapsed), usage),
+        None => format!("wall {}", exec::millis(output.elapsed)),
+    };
+    // A wrong output is shown where it differs from the expected one
+    let difference = match (&test.answer, judgement.verdict) {
+        (Some(answer), Verdict::WrongAnswer | Verdict::PresentationError) => {
+            let answer = fs::read(answer)?;
+            diff::first_difference(report.compare, &output.stdout, &answer)
+                .map(|difference| difference.render(&output.stdout, &answer, &report.style))
+        }
+        _ => None,
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    // stderr is only shown when it is not saved next to the output
+    let show_stderr = output_file.is_none() && !report.live;
+    if let Some(output_file) = output_file {
+        print_cool(
+            &format!(
+                "{}: {} {} ({})",
+                test_name,
+                stats,
+                judgement,
+                output_file.display()
+            ),
+            &mut stdout,
+        )?;
+    } else {
+        print_cool(
+            &format!("{}: {} {}", test_name, stats, judgement),
+            &mut stdout,
+        )?;
+        // Already shown with --live
+        if difference.is_none() && !report.live {
+            show(&output.stdout, report.hexdump, &mut stdout)?;
+        }
+    }
+    if let Some(difference) = difference {
+        stdout.write_all(difference.as_bytes())?;
+    }
+    if !output.stderr.is_empty() && show_stderr {
+        print_cool("(stderr)", &mut stdout)?;
+        show(&output.stderr, report.hexdump, &mut stdout)?;
+    }
+    Ok(())
+}
+
+/// Writes `bytes` for the terminal, with the ones that are not UTF-8 replaced,
+/// or as a hexdump.
+fn show<W: Write>(bytes: &[u8], hexdump: bool, out: &mut W) -> Result<()> {
+    if !hexdump {
+        out.write_all(String::from_utf8_lossy(bytes).as_bytes())?;
+        return Ok(());
+    }
+    // `00000010: 3120 320a 3320 340a                      1 2.3 4.`
+    for (i, row) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (j, byte) in row.iter().enumerate() {
+            if j % 2 == 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{byte:02x}"));
+        }
+        let text: String = row
+            .iter()
+            .map(|&byte| match byte {
+                b' ' | b'!'..=b'~' => byte as char,
+                _ => '.',
+            })
+            .collect();
+        writeln!(out, "{:08x}:{hex:<40}  {text}", i * 16)?;
+    }
+    Ok(())
+}
+
+fn terminal_width() -> usize {
+    if let Some((w, _)) = term_size::dimensions() {
+        w
+    } else {
+        100
+    }
+}
+
+fn print_cool<W: Write>(mid: &str, stdout: &mut W) -> Result<()> {
+    let col = terminal_width();
+    let occupied = 4 + mid.len();
+    let n1 = col.saturating_sub(occupied) / 2;
+    let occupied = 4 + mid.len() + n1;
+    let n2 = col.saturating_sub(occupied);
+    stdout.write_fmt(format_args!(
+        "{}> {} <{}\n",
+        "-".repeat(n1),
+        mid,
+        "-".repeat(n2),
+    ))?;
+    Ok(())
+}
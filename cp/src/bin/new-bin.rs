@@ -1,21 +1,22 @@
-use std::{env, fs, io::Write, path};
+use std::{env, fs, io::Write};
 
 fn main() {
-    let mut args = env::args().into_iter();
+    let mut args = env::args_os();
     let file_name = args.nth(1).expect("Enter file_path");
-    let content = match args.next().unwrap_or("0".to_string()).as_str() {
+    let version = args.next().unwrap_or("0".into());
+    let content = match version.to_string_lossy().as_ref() {
         "0" => include_bytes!("template.rs").to_vec(),
         "1" => include_bytes!("template_local.rs").to_vec(),
         "2" => include_bytes!("template_safe.rs").to_vec(),
         v => panic!("Invalid version: {}. 0..=2 are available", v),
     };
-    let current_dir = env::current_dir()
+    let mut name = file_name;
+    name.push(".rs");
+    let path = env::current_dir()
         .expect("Can't get current directory")
-        .into_os_string()
-        .into_string()
-        .unwrap();
-    let path = format!("{}/src/bin/{}.rs", current_dir, file_name);
-    let path = path::Path::new(&path);
+        .join("src")
+        .join("bin")
+        .join(name);
     if path.is_file() {
         panic!("File {} already exists", path.display());
     }
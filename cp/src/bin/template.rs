@@ -25,7 +25,10 @@ impl<R: io::BufRead> Scanner<R> {
                 .expect("Failed read");
             self.buf_iter = unsafe {
                 let slice = str::from_utf8_unchecked(&self.buf_str);
-                std::mem::transmute(slice.split_ascii_whitespace())
+                std::mem::transmute::<
+                    str::SplitAsciiWhitespace<'_>,
+                    str::SplitAsciiWhitespace<'static>,
+                >(slice.split_ascii_whitespace())
             }
         }
     }
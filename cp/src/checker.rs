@@ -0,0 +1,115 @@
+use crate::{
+    exec::{self, Program},
+    verdict::{Judgement, Verdict},
+};
+use std::{fs, io, path::Path};
+
+/// A testlib-compatible checker, called as `checker input output answer`.
+#[derive(Clone)]
+pub struct Checker {
+    program: Program,
+}
+
+impl Checker {
+    pub fn new(program: Program) -> Self {
+        Checker { program }
+    }
+
+    pub fn check(&self, input: &Path, output: &[u8], answer: &Path) -> io::Result<Judgement> {
+        let output_file = exec::temp_file("out");
+        fs::write(&output_file, output)?;
+        let result = self
+            .program
+            .command()
+            .arg(input)
+            .arg(&output_file)
+            .arg(answer)
+            .output();
+        fs::remove_file(&output_file)?;
+        let result = result?;
+        let comment = String::from_utf8_lossy(&result.stderr)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        Ok(testlib_judgement(result.status.code(), comment))
+    }
+}
+
+/// Reads the exit code of a checker the way testlib writes it.
+pub fn testlib_judgement(code: Option<i32>, comment: String) -> Judgement {
+    let verdict = match code {
+        Some(0) => Verdict::Accepted,
+        Some(1) => Verdict::WrongAnswer,
+        Some(2) => Verdict::PresentationError,
+        Some(7) => Verdict::Partial,
+        Some(code) if code >= 16 => Verdict::Partial,
+        _ => Verdict::CheckerFailed,
+    };
+    let mut comment = comment;
+    let mut points = None;
+    if verdict == Verdict::Partial {
+        // `quitp` starts its message with the points
+        let (first, rest) = comment.split_once(' ').unwrap_or((&comment, ""));
+        if let Ok(value) = first.parse() {
+            points = Some(value);
+            comment = rest.trim().to_string();
+        } else if let Some(code) = code.filter(|&code| code >= 16) {
+            points = Some((code - 16) as f64);
+        }
+    }
+    Judgement {
+        verdict,
+        points,
+        comment: (!comment.is_empty()).then_some(comment),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn judgement(code: Option<i32>, comment: &str) -> (Verdict, Option<f64>, Option<String>) {
+        let judgement = testlib_judgement(code, comment.to_string());
+        (judgement.verdict, judgement.points, judgement.comment)
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(
+            judgement(Some(0), "ok"),
+            (Verdict::Accepted, None, Some("ok".into()))
+        );
+        assert_eq!(judgement(Some(1), ""), (Verdict::WrongAnswer, None, None));
+        assert_eq!(
+            judgement(Some(2), ""),
+            (Verdict::PresentationError, None, None)
+        );
+        assert_eq!(judgement(Some(3), ""), (Verdict::CheckerFailed, None, None));
+        assert_eq!(judgement(None, ""), (Verdict::CheckerFailed, None, None));
+    }
+
+    #[test]
+    fn partial_points() {
+        // `quitp` writes the points first
+        assert_eq!(
+            judgement(Some(7), "0.5 half of it"),
+            (Verdict::Partial, Some(0.5), Some("half of it".into()))
+        );
+        assert_eq!(
+            judgement(Some(7), "almost"),
+            (Verdict::Partial, None, Some("almost".into()))
+        );
+        // Otherwise they are the exit code minus 16
+        assert_eq!(judgement(Some(16), ""), (Verdict::Partial, Some(0.0), None));
+        assert_eq!(
+            judgement(Some(41), "almost"),
+            (Verdict::Partial, Some(25.0), Some("almost".into()))
+        );
+        assert_eq!(
+            judgement(Some(41), "3 of 5"),
+            (Verdict::Partial, Some(3.0), Some("of 5".into()))
+        );
+    }
+}
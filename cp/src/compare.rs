@@ -0,0 +1,180 @@
+use std::{fmt, str};
+
+static DEFAULT_EPSILON: f64 = 1e-6;
+
+/// How the output of a test is compared with its expected answer.
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum Mode {
+    /// Byte by byte.
+    Exact,
+    /// Token by token, ignoring any difference in whitespace.
+    #[default]
+    Tokens,
+    /// Like `Tokens`, but numbers may differ by an absolute or relative `epsilon`.
+    Float { epsilon: f64 },
+    /// Like `Tokens`, but `YES` and `yes` are the same.
+    CaseInsensitive,
+    /// The same lines in any order.
+    Lines,
+}
+
+impl Mode {
+    pub fn matches(self, output: &[u8], answer: &[u8]) -> bool {
+        match self {
+            Mode::Exact => output == answer,
+            Mode::Lines => sorted_lines(output) == sorted_lines(answer),
+            _ => zip_tokens(output, answer, |a, b| self.token_eq(a, b)),
+        }
+    }
+
+    /// Whether two tokens are the same for the token-wise modes.
+    pub fn token_eq(self, output: &[u8], answer: &[u8]) -> bool {
+        match self {
+            Mode::Float { epsilon } => {
+                output == answer || float_eq(output, answer, epsilon).unwrap_or(false)
+            }
+            Mode::CaseInsensitive => output.eq_ignore_ascii_case(answer),
+            _ => output == answer,
+        }
+    }
+}
+
+impl str::FromStr for Mode {
+    type Err = String;
+
+    /// `exact`, `tokens`, `float`, `float:<epsilon>`, `icase` or `lines`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, epsilon) = match s.split_once(':') {
+            Some((name, epsilon)) => (name, Some(epsilon)),
+            None => (s, None),
+        };
+        match (name, epsilon) {
+            ("exact", None) => Ok(Mode::Exact),
+            ("tokens", None) => Ok(Mode::Tokens),
+            ("float", None) => Ok(Mode::Float {
+                epsilon: DEFAULT_EPSILON,
+            }),
+            ("float", Some(epsilon)) => match epsilon.parse() {
+                Ok(epsilon) => Ok(Mode::Float { epsilon }),
+                Err(_) => Err(format!("Invalid epsilon {epsilon}")),
+            },
+            ("icase", None) => Ok(Mode::CaseInsensitive),
+            ("lines", None) => Ok(Mode::Lines),
+            _ => Err(format!("Invalid comparison mode {s}")),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mode::Exact => write!(f, "exact"),
+            Mode::Tokens => write!(f, "tokens"),
+            Mode::Float { epsilon } => write!(f, "float:{epsilon}"),
+            Mode::CaseInsensitive => write!(f, "icase"),
+            Mode::Lines => write!(f, "lines"),
+        }
+    }
+}
+
+fn tokens(buf: &[u8]) -> impl Iterator<Item = &[u8]> {
+    buf.split(u8::is_ascii_whitespace).filter(|t| !t.is_empty())
+}
+
+fn zip_tokens<F: Fn(&[u8], &[u8]) -> bool>(output: &[u8], answer: &[u8], eq: F) -> bool {
+    let mut output = tokens(output);
+    let mut answer = tokens(answer);
+    loop {
+        match (output.next(), answer.next()) {
+            (Some(a), Some(b)) if eq(a, b) => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+fn float_eq(output: &[u8], answer: &[u8], epsilon: f64) -> Option<bool> {
+    let output: f64 = str::from_utf8(output).ok()?.parse().ok()?;
+    let answer: f64 = str::from_utf8(answer).ok()?.parse().ok()?;
+    let diff = (output - answer).abs();
+    Some(diff <= epsilon || diff <= epsilon * answer.abs())
+}
+
+/// Lines without trailing whitespace, skipping the empty ones.
+fn sorted_lines(buf: &[u8]) -> Vec<&[u8]> {
+    let mut lines: Vec<_> = significant_lines(buf).map(|(_, line)| line).collect();
+    lines.sort_unstable();
+    lines
+}
+
+/// The lines that count for `Mode::Lines` with their index, without trailing
+/// whitespace.
+pub(crate) fn significant_lines(buf: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    buf.split(|&c| c == b'\n')
+        .map(|line| line.trim_ascii_end())
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(mode: &str, output: &str, answer: &str) -> bool {
+        let mode: Mode = mode.parse().unwrap();
+        mode.matches(output.as_bytes(), answer.as_bytes())
+    }
+
+    #[test]
+    fn modes_are_parsed() {
+        assert_eq!("exact".parse(), Ok(Mode::Exact));
+        assert_eq!("tokens".parse(), Ok(Mode::Tokens));
+        assert_eq!("icase".parse(), Ok(Mode::CaseInsensitive));
+        assert_eq!("lines".parse(), Ok(Mode::Lines));
+        assert_eq!("float".parse(), Ok(Mode::Float { epsilon: 1e-6 }));
+        assert_eq!("float:1e-9".parse(), Ok(Mode::Float { epsilon: 1e-9 }));
+        assert!("float:x".parse::<Mode>().is_err());
+        assert!("exact:1".parse::<Mode>().is_err());
+        assert!("words".parse::<Mode>().is_err());
+        // Written back the way it is read
+        let mode = Mode::Float { epsilon: 1e-9 };
+        assert_eq!(mode.to_string().parse(), Ok(mode));
+    }
+
+    #[test]
+    fn exact_and_tokens() {
+        assert!(matches("exact", "1 2\n", "1 2\n"));
+        assert!(!matches("exact", "1 2", "1 2\n"));
+        assert!(matches("tokens", "1  2\r\n\n", "1 2"));
+        assert!(!matches("tokens", "1 2", "1 2 3"));
+        assert!(!matches("tokens", "12", "1 2"));
+        assert!(matches("icase", "Yes", "YES"));
+        assert!(!matches("tokens", "Yes", "YES"));
+    }
+
+    #[test]
+    fn floats_differ_by_an_absolute_or_relative_epsilon() {
+        // Absolute near zero
+        assert!(matches("float:1e-6", "0.0000005", "0"));
+        assert!(!matches("float:1e-6", "0.000002", "0"));
+        // Relative for large answers
+        assert!(matches("float:1e-6", "1000000.5", "1000000"));
+        assert!(!matches("float:1e-6", "1000002", "1000000"));
+        // Tokens that are not numbers are compared as they are
+        assert!(matches("float", "1.5 abc", "1.5000001 abc"));
+        assert!(!matches("float", "abc", "abd"));
+        assert!(!matches("float", "nan", "1"));
+    }
+
+    #[test]
+    fn lines_are_a_multiset() {
+        assert!(matches("lines", "b\na\n", "a\nb"));
+        // Trailing whitespace and empty lines don't count
+        assert!(matches("lines", "a  \n\nb\n", "b\na"));
+        // But repeated lines do
+        assert!(!matches("lines", "a\na\nb", "a\nb\nb"));
+        assert!(!matches("lines", "a\nb", "a\nb\nb"));
+        // Lines are not tokens
+        assert!(!matches("lines", "a b", "a  b"));
+    }
+}
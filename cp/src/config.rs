@@ -0,0 +1,220 @@
+use crate::{
+    compare,
+    lang::{Languages, Toolchain},
+    testcase::{Glob, Naming, Pattern},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use toml::{Table, Value};
+
+static CONFIG_FILE: &str = "cp.toml";
+
+/// Project settings, read from `cp.toml` or from the `[package.metadata.cp]`
+/// table of `Cargo.toml`:
+///
+/// ```toml
+/// time-limit = 2000    # ms
+/// memory-limit = 256   # MB
+/// parallel = 4         # threads, 0 for all of them like `-p0`
+/// compare = "float:1e-9"
+/// test-folders = ["input*", "test*", "sample*"]
+/// output-dir = "output"
+/// keep-runs = true     # outputs in a new `output-dir/<time>` every run
+///
+/// [[tests]]            # input and answer names, tried in order
+/// input = "*.in"       # `*` anything, `?` a character, `{n}` a number
+/// answers = ["*.ans", "*.out"]
+///
+/// [cpp]                # also `rust`, `c` and `python`
+/// compiler = "g++"
+/// debug = ["-O0", "-g"]
+/// release = ["-O2"]
+/// warnings = ["-Wall"]
+/// link = []
+/// ```
+#[derive(Clone)]
+pub struct Config {
+    pub time_limit: Option<Duration>,
+    /// In bytes.
+    pub memory_limit: Option<u64>,
+    pub parallel: Option<usize>,
+    pub compare: compare::Mode,
+    pub naming: Naming,
+    pub output_dir: String,
+    /// Every run writes its outputs in a new folder of `output_dir`.
+    pub keep_runs: bool,
+    pub languages: Languages,
+    /// Where the settings come from, if anywhere.
+    pub path: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            time_limit: None,
+            memory_limit: None,
+            parallel: None,
+            compare: compare::Mode::default(),
+            naming: Naming::default(),
+            output_dir: "output".to_string(),
+            keep_runs: false,
+            languages: Languages::default(),
+            path: None,
+        }
+    }
+}
+
+impl Config {
+    /// The settings of the closest of `dir` and its parents having a `cp.toml`
+    /// or a `Cargo.toml` with a `[package.metadata.cp]` table. The defaults if
+    /// none has them.
+    pub fn find(dir: &Path) -> io::Result<Config> {
+        for dir in dir.ancestors() {
+            let path = dir.join(CONFIG_FILE);
+            if path.is_file() {
+                return Config::load(&path, &read_table(&path)?);
+            }
+            let path = dir.join("Cargo.toml");
+            if path.is_file() {
+                let table = read_table(&path)?;
+                if let Some(settings) = cargo_metadata(&table) {
+                    return Config::load(&path, settings);
+                }
+            }
+        }
+        Ok(Config::default())
+    }
+
+    fn load(path: &Path, settings: &Table) -> io::Result<Config> {
+        let mut config = Config::default();
+        config
+            .apply(settings)
+            .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))?;
+        config.path = Some(path.to_path_buf());
+        Ok(config)
+    }
+
+    fn apply(&mut self, settings: &Table) -> Result<(), String> {
+        for (key, value) in settings {
+            match key.as_str() {
+                "time-limit" => self.time_limit = Some(Duration::from_millis(integer(key, value)?)),
+                "memory-limit" => self.memory_limit = Some(integer(key, value)? * 1024 * 1024),
+                "parallel" => self.parallel = Some(integer(key, value)? as usize),
+                "compare" => {
+                    self.compare = string(key, value)?
+                        .parse()
+                        .map_err(|e| format!("`{key}`: {e}"))?
+                }
+                "tests" => self.naming.patterns = patterns(key, value)?,
+                "test-folders" => {
+                    self.naming.folders = strings(key, value)?
+                        .iter()
+                        .map(|folder| glob(key, &folder.to_ascii_lowercase()))
+                        .collect::<Result<_, _>>()?
+                }
+                "output-dir" => self.output_dir = string(key, value)?,
+                "keep-runs" => self.keep_runs = boolean(key, value)?,
+                "rust" => apply_toolchain(&mut self.languages.rust, key, value)?,
+                "cpp" => apply_toolchain(&mut self.languages.cpp, key, value)?,
+                "c" => apply_toolchain(&mut self.languages.c, key, value)?,
+                "python" => apply_toolchain(&mut self.languages.python, key, value)?,
+                _ => return Err(format!("unknown setting `{key}`")),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn apply_toolchain(toolchain: &mut Toolchain, name: &str, value: &Value) -> Result<(), String> {
+    let table = value
+        .as_table()
+        .ok_or_else(|| format!("`{name}` should be a table"))?;
+    for (key, value) in table {
+        match key.as_str() {
+            "compiler" => toolchain.compiler = string(key, value)?,
+            "debug" => toolchain.debug = strings(key, value)?,
+            "release" => toolchain.release = strings(key, value)?,
+            "warnings" => toolchain.warnings = strings(key, value)?,
+            "link" => toolchain.link = strings(key, value)?,
+            _ => return Err(format!("unknown setting `{name}.{key}`")),
+        }
+    }
+    Ok(())
+}
+
+fn cargo_metadata(table: &Table) -> Option<&Table> {
+    table.get("package")?.get("metadata")?.get("cp")?.as_table()
+}
+
+fn patterns(key: &str, value: &Value) -> Result<Vec<Pattern>, String> {
+    let tables = value
+        .as_array()
+        .ok_or_else(|| format!("`{key}` should be an array of tables"))?;
+    tables
+        .iter()
+        .map(|table| {
+            let table = table
+                .as_table()
+                .ok_or_else(|| format!("`{key}` should be an array of tables"))?;
+            let input = table
+                .get("input")
+                .ok_or_else(|| format!("`{key}` without an `input`"))?;
+            let answers = match table.get("answers") {
+                Some(answers) => strings("answers", answers)?,
+                None => Vec::new(),
+            };
+            Ok(Pattern {
+                input: glob("input", &string("input", input)?)?,
+                answers: answers
+                    .iter()
+                    .map(|answer| glob("answers", answer))
+                    .collect::<Result<_, _>>()?,
+            })
+        })
+        .collect()
+}
+
+fn glob(key: &str, pattern: &str) -> Result<Glob, String> {
+    pattern.parse().map_err(|e| format!("`{key}`: {e}"))
+}
+
+fn read_table(path: &Path) -> io::Result<Table> {
+    fs::read_to_string(path)?
+        .parse()
+        .map_err(|e| io::Error::other(format!("{}: {}", path.display(), e)))
+}
+
+fn integer(key: &str, value: &Value) -> Result<u64, String> {
+    value
+        .as_integer()
+        .and_then(|value| u64::try_from(value).ok())
+        .ok_or_else(|| format!("`{key}` should be a non-negative integer"))
+}
+
+fn boolean(key: &str, value: &Value) -> Result<bool, String> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("`{key}` should be a boolean"))
+}
+
+fn string(key: &str, value: &Value) -> Result<String, String> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("`{key}` should be a string"))
+}
+
+fn strings(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    value
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect()
+        })
+        .ok_or_else(|| format!("`{key}` should be an array of strings"))
+}
@@ -0,0 +1,321 @@
+use crate::compare::{self, Mode};
+use std::{collections::HashMap, fmt};
+
+static RED: &str = "\x1b[31m";
+static GREEN: &str = "\x1b[32m";
+static HIGHLIGHT: &str = "\x1b[1;4m";
+static END_HIGHLIGHT: &str = "\x1b[22;24m";
+static RESET: &str = "\x1b[0m";
+
+/// A place in an output, from 0, with what is there: a token, a byte or a line
+/// depending on the comparison mode, `None` at the end of the output.
+pub struct Spot {
+    pub line: usize,
+    pub column: usize,
+    pub text: Option<Vec<u8>>,
+}
+
+/// Where an output first differs from its expected answer.
+pub struct Difference {
+    pub expected: Spot,
+    pub found: Spot,
+}
+
+/// How to show a difference.
+#[derive(Copy, Clone)]
+pub struct Style {
+    /// Expected and found next to each other instead of one after the other.
+    pub side_by_side: bool,
+    pub color: bool,
+    /// In characters.
+    pub width: usize,
+    /// Lines shown before and after the difference.
+    pub context: usize,
+}
+
+/// The first difference between `output` and `answer` as `mode` sees them:
+/// token by token, byte by byte for `Mode::Exact`, and the first line that is
+/// not in the other one for `Mode::Lines`.
+pub fn first_difference(mode: Mode, output: &[u8], answer: &[u8]) -> Option<Difference> {
+    match mode {
+        Mode::Exact => first_byte(output, answer),
+        Mode::Lines => first_line(output, answer),
+        _ => first_token(mode, output, answer),
+    }
+}
+
+fn first_token(mode: Mode, output: &[u8], answer: &[u8]) -> Option<Difference> {
+    let mut output_tokens = tokens(output);
+    let mut answer_tokens = tokens(answer);
+    loop {
+        match (output_tokens.next(), answer_tokens.next()) {
+            (None, None) => return None,
+            (Some((_, a)), Some((_, b))) if mode.token_eq(a, b) => {}
+            (found, expected) => {
+                return Some(Difference::new(spot(answer, expected), spot(output, found)))
+            }
+        }
+    }
+}
+
+fn first_byte(output: &[u8], answer: &[u8]) -> Option<Difference> {
+    let offset = output
+        .iter()
+        .zip(answer)
+        .position(|(a, b)| a != b)
+        .unwrap_or(output.len().min(answer.len()));
+    if offset == output.len() && offset == answer.len() {
+        return None;
+    }
+    let at = |buf: &'_ [u8]| spot(buf, buf.get(offset..=offset).map(|byte| (offset, byte)));
+    Some(Difference::new(at(answer), at(output)))
+}
+
+fn first_line(output: &[u8], answer: &[u8]) -> Option<Difference> {
+    let (expected, found) = (missing_line(answer, output), missing_line(output, answer));
+    if expected.is_none() && found.is_none() {
+        return None;
+    }
+    let spot = |buf: &[u8], line: Option<(usize, &[u8])>| match line {
+        Some((line, text)) => Spot {
+            line,
+            column: 0,
+            text: Some(text.to_vec()),
+        },
+        None => spot(buf, None),
+    };
+    Some(Difference {
+        expected: spot(answer, expected),
+        found: spot(output, found),
+    })
+}
+
+/// The first line of `buf` that `other` has less times.
+fn missing_line<'a>(buf: &'a [u8], other: &[u8]) -> Option<(usize, &'a [u8])> {
+    let mut counts = HashMap::new();
+    for (_, line) in compare::significant_lines(other) {
+        *counts.entry(line).or_insert(0) += 1;
+    }
+    compare::significant_lines(buf).find(|(_, line)| match counts.get_mut(line) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            false
+        }
+        _ => true,
+    })
+}
+
+/// The tokens of `buf` with their offsets.
+fn tokens(buf: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    buf.split(u8::is_ascii_whitespace)
+        .map(move |token| {
+            let start = offset;
+            offset += token.len() + 1;
+            (start, token)
+        })
+        .filter(|(_, token)| !token.is_empty())
+}
+
+/// The spot of what is at an offset of `buf`, or of its end, right after its
+/// last non-whitespace character.
+fn spot(buf: &[u8], at: Option<(usize, &[u8])>) -> Spot {
+    let (offset, text) = match at {
+        Some((offset, text)) => (offset, Some(text.to_vec())),
+        None => (buf.trim_ascii_end().len(), None),
+    };
+    let before = &buf[..offset];
+    let line_start = before
+        .iter()
+        .rposition(|&c| c == b'\n')
+        .map_or(0, |i| i + 1);
+    Spot {
+        line: before.iter().filter(|&&c| c == b'\n').count(),
+        column: offset - line_start,
+        text,
+    }
+}
+
+impl fmt::Display for Spot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.text {
+            Some(text) => write!(f, "`{}`", text.escape_ascii())?,
+            None => write!(f, "end of output")?,
+        }
+        write!(f, " at line {}, column {}", self.line + 1, self.column + 1)
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected {}, found {}", self.expected, self.found)
+    }
+}
+
+impl Difference {
+    /// When one output ends early, its end is put where the other one goes on,
+    /// so that both line up, unless that is past its last line.
+    fn new(mut expected: Spot, mut found: Spot) -> Self {
+        line_up(&mut expected, &found);
+        line_up(&mut found, &expected);
+        Difference { expected, found }
+    }
+
+    /// The lines around the difference in both outputs, with the differing
+    /// text highlighted.
+    pub fn render(&self, output: &[u8], answer: &[u8], style: &Style) -> String {
+        let mut out = format!("{self}\n");
+        if style.side_by_side {
+            self.side_by_side(output, answer, style, &mut out);
+        } else {
+            self.unified(output, answer, style, &mut out);
+        }
+        out
+    }
+
+    /// The lines before the difference, then the expected ones with `-` and
+    /// the ones found with `+`, or once when they are the same.
+    fn unified(&self, output: &[u8], answer: &[u8], style: &Style, out: &mut String) {
+        let (expected, found) = (&self.expected, &self.found);
+        let digits = number_width(expected.line.max(found.line) + style.context);
+        // `- 12| `
+        let width = style.width.saturating_sub(digits + 4);
+        let before = style.context.min(found.line);
+        for (i, line) in lines(output, found.line - before, found.line) {
+            let (text, ..) = paint(line, found.column, None, width, None);
+            out.push_str(&format!("  {:>digits$}| {text}\n", i + 1));
+        }
+        let left = window(answer, expected.line, style.context + 1);
+        let right = window(output, found.line, style.context + 1);
+        for (row, (left, right)) in left.into_iter().zip(right).enumerate() {
+            if row > 0 && left.is_some() && left.map(|(_, l)| l) == right.map(|(_, r)| r) {
+                let (i, line) = right.unwrap_or_default();
+                let (text, ..) = paint(line, found.column, None, width, None);
+                out.push_str(&format!("  {:>digits$}| {text}\n", i + 1));
+                continue;
+            }
+            for (sign, side, spot, color) in
+                [("-", left, expected, RED), ("+", right, found, GREEN)]
+            {
+                let color = style.color.then_some(color);
+                let (i, line) = match side {
+                    Some(side) => side,
+                    None if row == 0 => (spot.line, &b"(end of output)"[..]),
+                    None => continue,
+                };
+                let highlight = (row == 0 && side.is_some()).then_some(spot);
+                let (text, _, caret) = paint(line, spot.column, highlight, width, color);
+                out.push_str(&format!("{sign} {:>digits$}| {text}\n", i + 1));
+                if let (Some(caret), None) = (caret, color) {
+                    out.push_str(&format!("{:1$}^\n", "", digits + 4 + caret));
+                }
+            }
+        }
+    }
+
+    /// The expected lines on the left and the ones found on the right, the
+    /// differing ones aligned on the same row.
+    fn side_by_side(&self, output: &[u8], answer: &[u8], style: &Style, out: &mut String) {
+        let (expected, found) = (&self.expected, &self.found);
+        let digits = number_width(expected.line.max(found.line) + style.context);
+        // `12| ` on both sides and ` | ` between them
+        let half = style.width.saturating_sub(3) / 2;
+        let width = half.saturating_sub(digits + 2);
+        let before = style.context.min(expected.line).min(found.line);
+        let rows = before + style.context + 1;
+        let left = window(answer, expected.line - before, rows);
+        let right = window(output, found.line - before, rows);
+        for (row, (left, right)) in left.into_iter().zip(right).enumerate() {
+            if left.is_none() && right.is_none() {
+                continue;
+            }
+            let differ = row == before || left.map(|(_, l)| l) != right.map(|(_, r)| r);
+            let cell = |side: Option<(usize, &[u8])>, spot: &Spot, color| match side {
+                Some((i, line)) => {
+                    let highlight = (i == spot.line).then_some(spot);
+                    let color = (style.color && differ).then_some(color);
+                    let (text, len, _) = paint(line, spot.column, highlight, width, color);
+                    let padding = width.saturating_sub(len);
+                    format!("{:>digits$}| {text}{:padding$}", i + 1, "")
+                }
+                None => format!("{:1$}", "", half),
+            };
+            let left = cell(left, expected, RED);
+            let right = cell(right, found, GREEN);
+            let separator = if differ { '|' } else { ' ' };
+            out.push_str(format!("{left} {separator} {right}").trim_end());
+            out.push('\n');
+        }
+    }
+}
+
+fn line_up(end: &mut Spot, other: &Spot) {
+    let before = (end.line, end.column) < (other.line, other.column);
+    if end.text.is_none() && before && other.line <= end.line + 1 {
+        (end.line, end.column) = (other.line, other.column);
+    }
+}
+
+fn number_width(n: usize) -> usize {
+    (n + 1).to_string().len()
+}
+
+/// `rows` lines of `buf` from `first`, `None` past its end.
+fn window(buf: &[u8], first: usize, rows: usize) -> Vec<Option<(usize, &[u8])>> {
+    let mut window: Vec<_> = lines(buf, first, first + rows).map(Some).collect();
+    window.resize(rows, None);
+    window
+}
+
+/// The lines `from..to` of `buf`, with their index.
+fn lines(buf: &[u8], from: usize, to: usize) -> impl Iterator<Item = (usize, &[u8])> {
+    let buf = buf.strip_suffix(b"\n").unwrap_or(buf);
+    buf.split(|&c| c == b'\n')
+        .enumerate()
+        .skip(from)
+        .take(to.saturating_sub(from))
+        .map(|(i, line)| (i, line.strip_suffix(b"\r").unwrap_or(line)))
+}
+
+/// `line` cut to `width` characters around `column`, in `color`, with the text
+/// of `highlight` highlighted. Also returns how many characters it takes and
+/// where the highlight starts.
+fn paint(
+    line: &[u8],
+    column: usize,
+    highlight: Option<&Spot>,
+    width: usize,
+    color: Option<&str>,
+) -> (String, usize, Option<usize>) {
+    let (start, end) = if line.len() <= width {
+        (0, line.len())
+    } else {
+        // Room for the `...` on both sides
+        let inner = width.saturating_sub(6).max(1).min(line.len());
+        let start = column.saturating_sub(inner / 3).min(line.len() - inner);
+        (start, start + inner)
+    };
+    let (from, to) = match highlight {
+        Some(spot) => {
+            let from = column.clamp(start, end);
+            let len = spot.text.as_ref().map_or(0, Vec::len);
+            (from, (column + len).clamp(from, end))
+        }
+        None => (end, end),
+    };
+    let ellipsis = |cut: bool| if cut { "..." } else { "" };
+    let lossy = |range: std::ops::Range<usize>| String::from_utf8_lossy(&line[range]).into_owned();
+    let before = ellipsis(start > 0).to_string() + &lossy(start..from);
+    let highlighted = lossy(from..to);
+    let after = lossy(to..end) + ellipsis(end < line.len());
+    let caret = highlight.map(|_| before.chars().count());
+    let len = before.chars().count() + highlighted.chars().count() + after.chars().count();
+    let text = match color {
+        Some(color) if from < to => {
+            format!("{color}{before}{HIGHLIGHT}{highlighted}{END_HIGHLIGHT}{after}{RESET}")
+        }
+        Some(color) => format!("{color}{before}{highlighted}{after}{RESET}"),
+        None => before + &highlighted + &after,
+    };
+    (text, len, caret)
+}
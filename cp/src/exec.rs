@@ -0,0 +1,426 @@
+use std::{
+    env,
+    ffi::OsString,
+    fmt,
+    fs::File,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+static POLL_INTERVAL: Duration = Duration::from_millis(2);
+
+static UNIQUE_NAMES: AtomicUsize = AtomicUsize::new(0);
+
+/// A binary with the arguments it always gets, like `python3 sol.py`.
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub binary: PathBuf,
+    pub args: Vec<OsString>,
+}
+
+impl Program {
+    pub fn new<P: Into<PathBuf>>(binary: P) -> Self {
+        Program {
+            binary: binary.into(),
+            args: Vec::new(),
+        }
+    }
+
+    pub fn arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// A command running the program, with nothing else set.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.binary);
+        command.args(&self.args);
+        command
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Limits {
+    pub time: Option<Duration>,
+    /// Peak memory in bytes.
+    pub memory: Option<u64>,
+    /// Whether `memory` is also applied to the child as an address-space rlimit.
+    /// Sanitized binaries reserve terabytes of address space at startup, so for
+    /// them the limit can only be checked against the peak RSS afterwards.
+    pub enforce_memory: bool,
+    /// Once set, the child is killed, for runs that are not wanted anymore.
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// Also pass the output of the child through as it comes, see `Echo`.
+    pub live: bool,
+}
+
+/// Resource usage of a finished child, as reported by `wait4`.
+#[derive(Copy, Clone, Default)]
+pub struct Usage {
+    /// Peak resident set size in bytes.
+    pub max_rss: u64,
+    pub user: Duration,
+    pub system: Duration,
+}
+
+impl Usage {
+    /// CPU time, in user and kernel mode.
+    pub fn cpu(&self) -> Duration {
+        self.user + self.system
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "cpu {} {:.1}MB",
+            millis(self.cpu()),
+            self.max_rss as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+/// A file name that is unique to this process and call, like `cp-42-7.bin`.
+pub fn unique_name(extension: &str) -> String {
+    let id = UNIQUE_NAMES.fetch_add(1, Ordering::Relaxed);
+    format!("cp-{}-{}.{}", std::process::id(), id, extension)
+}
+
+/// A path in the temporary directory that is unique to this process and call.
+pub fn temp_file(extension: &str) -> PathBuf {
+    env::temp_dir().join(unique_name(extension))
+}
+
+/// `1.234567s` becomes `1234.567ms`.
+pub fn millis(duration: Duration) -> String {
+    format!("{:.3}ms", duration.as_secs_f64() * 1000.0)
+}
+
+pub struct Execution {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    pub status: ExitStatus,
+    /// Wall time, from just before the spawn to the exit.
+    pub elapsed: Duration,
+    pub timed_out: bool,
+    /// Only available on Linux.
+    pub usage: Option<Usage>,
+}
+
+impl Execution {
+    /// Describes why the child failed: the panic message of a Rust program, the
+    /// sanitizer summary, the terminating signal or the exit code.
+    pub fn runtime_error(&self) -> Option<String> {
+        if self.status.success() {
+            return None;
+        }
+        let stderr = String::from_utf8_lossy(&self.stderr);
+        if let Some(panic) = panic_summary(&stderr) {
+            return Some(panic);
+        }
+        if let Some(line) = stderr.lines().find(|line| line.starts_with("SUMMARY: ")) {
+            return Some(line["SUMMARY: ".len()..].to_string());
+        }
+        Some(self.exit_status())
+    }
+
+    /// `exit code 0`, or the signal that terminated the child.
+    pub fn exit_status(&self) -> String {
+        if let Some(signal) = signal(&self.status) {
+            return signal_name(signal);
+        }
+        match self.status.code() {
+            Some(code) => format!("exit code {code}"),
+            None => "killed".to_string(),
+        }
+    }
+}
+
+/// `thread 'main' panicked at src/main.rs:4:5:\nmessage` becomes
+/// `panicked at src/main.rs:4:5: message`.
+fn panic_summary(stderr: &str) -> Option<String> {
+    let mut lines = stderr.lines();
+    let line = lines.find(|line| line.starts_with("thread '") && line.contains("panicked at"))?;
+    let at = &line[line.find("panicked at")?..];
+    if let Some(location) = at.strip_suffix(':') {
+        let message = lines.next().unwrap_or_default();
+        Some(format!("{location}: {message}"))
+    } else {
+        // Before Rust 1.73: `panicked at 'message', src/main.rs:4:5`
+        Some(at.to_string())
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+#[cfg(unix)]
+fn signal_name(signal: i32) -> String {
+    match signal {
+        libc::SIGSEGV => "SIGSEGV".to_string(),
+        libc::SIGABRT => "SIGABRT".to_string(),
+        libc::SIGFPE => "SIGFPE".to_string(),
+        libc::SIGBUS => "SIGBUS".to_string(),
+        libc::SIGILL => "SIGILL".to_string(),
+        libc::SIGKILL => "SIGKILL".to_string(),
+        libc::SIGTERM => "SIGTERM".to_string(),
+        libc::SIGPIPE => "SIGPIPE".to_string(),
+        libc::SIGXCPU => "SIGXCPU".to_string(),
+        signal => format!("signal {signal}"),
+    }
+}
+
+#[cfg(not(unix))]
+fn signal_name(signal: i32) -> String {
+    format!("signal {signal}")
+}
+
+/// Runs `program` feeding it `input`, killing it once it goes over the time limit.
+/// stdin is fed and stdout and stderr are drained while the child runs, so a
+/// child printing before it has read all of its input can't stall on a full
+/// pipe and be mistaken for a slow one.
+pub fn run(program: &Program, input: &[u8], limits: &Limits) -> io::Result<Execution> {
+    run_command(command(program, limits), input, limits)
+}
+
+/// Like `run`, with the file `input` as stdin, so that it is never loaded in
+/// memory.
+pub fn run_file(program: &Program, input: &Path, limits: &Limits) -> io::Result<Execution> {
+    let mut command = command(program, limits);
+    command.stdin(File::open(input)?);
+    run_command(command, &[], limits)
+}
+
+/// Like `run`, for a command built with `command`.
+pub fn run_command(mut command: Command, input: &[u8], limits: &Limits) -> io::Result<Execution> {
+    let start = Instant::now();
+    let mut child = command.spawn()?;
+    let stdout = drain(child.stdout.take(), limits.live.then_some(Echo::Stdout));
+    let stderr = drain(child.stderr.take(), limits.live.then_some(Echo::Stderr));
+    let stdin = child.stdin.take();
+    thread::scope(|scope| {
+        if let Some(mut stdin) = stdin {
+            // A child that exits without reading all of its input is judged by
+            // what it printed, not by the broken pipe.
+            scope.spawn(move || stdin.write_all(input));
+        }
+        let (status, timed_out, usage) =
+            wait(&mut child, start, limits.time, limits.cancel.as_deref())?;
+        let elapsed = start.elapsed();
+        Ok(Execution {
+            stdout: stdout.join().unwrap()?,
+            stderr: stderr.join().unwrap()?,
+            status,
+            elapsed,
+            timed_out,
+            usage,
+        })
+    })
+}
+
+/// A command for `program` with piped standard streams, under the memory limit.
+pub fn command(program: &Program, limits: &Limits) -> Command {
+    let mut command = program.command();
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if limits.enforce_memory {
+        if let Some(memory) = limits.memory {
+            limit_memory(&mut command, memory);
+        }
+    }
+    command
+}
+
+#[cfg(unix)]
+fn limit_memory(command: &mut Command, memory: u64) {
+    use std::os::unix::process::CommandExt;
+    let limit = libc::rlimit {
+        rlim_cur: memory as libc::rlim_t,
+        rlim_max: memory as libc::rlim_t,
+    };
+    // SAFETY: setrlimit is async-signal-safe and touches nothing but the child.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn limit_memory(_command: &mut Command, _memory: u64) {}
+
+/// Waits for `child`, killing it once `time_limit` has passed since `start` or
+/// once `cancel` is set. Returns its exit status, whether it went over the time
+/// limit and, on Linux, its usage.
+#[cfg(target_os = "linux")]
+pub fn wait(
+    child: &mut Child,
+    start: Instant,
+    time_limit: Option<Duration>,
+    cancel: Option<&AtomicBool>,
+) -> io::Result<(ExitStatus, bool, Option<Usage>)> {
+    use std::os::unix::process::ExitStatusExt;
+    let pid = child.id() as libc::pid_t;
+    let mut timed_out = false;
+    let mut killed = false;
+    loop {
+        let flags = if (time_limit.is_some() || cancel.is_some()) && !killed {
+            libc::WNOHANG
+        } else {
+            0
+        };
+        let mut status = 0;
+        // SAFETY: an all-zero rusage is a valid value for wait4 to overwrite.
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        let ret = unsafe { libc::wait4(pid, &mut status, flags, &mut rusage) };
+        if ret == -1 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err);
+        }
+        if ret == pid {
+            let usage = Usage {
+                max_rss: rusage.ru_maxrss as u64 * 1024,
+                user: timeval_to_duration(rusage.ru_utime),
+                system: timeval_to_duration(rusage.ru_stime),
+            };
+            return Ok((ExitStatus::from_raw(status), timed_out, Some(usage)));
+        }
+        if time_limit.is_some_and(|time_limit| start.elapsed() > time_limit) {
+            child.kill()?;
+            (timed_out, killed) = (true, true);
+            continue;
+        }
+        if cancelled(cancel) {
+            child.kill()?;
+            killed = true;
+            continue;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn cancelled(cancel: Option<&AtomicBool>) -> bool {
+    cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+}
+
+#[cfg(target_os = "linux")]
+fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn wait(
+    child: &mut Child,
+    start: Instant,
+    time_limit: Option<Duration>,
+    cancel: Option<&AtomicBool>,
+) -> io::Result<(ExitStatus, bool, Option<Usage>)> {
+    if time_limit.is_none() && cancel.is_none() {
+        return Ok((child.wait()?, false, None));
+    }
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status, false, None));
+        }
+        let timed_out = time_limit.is_some_and(|time_limit| start.elapsed() > time_limit);
+        if timed_out || cancelled(cancel) {
+            child.kill()?;
+            return Ok((child.wait()?, timed_out, None));
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Reads all of `pipe` on another thread, echoing it as it comes if asked to.
+pub fn drain<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    echo: Option<Echo>,
+) -> thread::JoinHandle<io::Result<Vec<u8>>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let mut pipe = match pipe {
+            Some(pipe) => pipe,
+            None => return Ok(buf),
+        };
+        let echo = match echo {
+            Some(echo) => echo,
+            None => {
+                pipe.read_to_end(&mut buf)?;
+                return Ok(buf);
+            }
+        };
+        let mut chunk = [0; 8192];
+        let mut line_start = true;
+        loop {
+            let read = match pipe.read(&mut chunk) {
+                Ok(0) => return Ok(buf),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            buf.extend_from_slice(&chunk[..read]);
+            echo.write(&chunk[..read], &mut line_start);
+        }
+    })
+}
+
+/// Where the output of a child goes when it is passed through, each line
+/// prefixed with the stream it comes from.
+#[derive(Copy, Clone)]
+pub enum Echo {
+    Stdout,
+    Stderr,
+}
+
+impl Echo {
+    /// Writes `chunk` with a prefix at the start of every line, right away so
+    /// that even a line that is not finished yet shows up. Failing to show it
+    /// doesn't change what the child printed, so errors are ignored.
+    fn write(self, chunk: &[u8], line_start: &mut bool) {
+        let prefix: &[u8] = match self {
+            Echo::Stdout => b"out| ",
+            Echo::Stderr => b"err| ",
+        };
+        let mut prefixed = Vec::with_capacity(chunk.len() + prefix.len());
+        for line in chunk.split_inclusive(|&c| c == b'\n') {
+            if *line_start {
+                prefixed.extend_from_slice(prefix);
+            }
+            prefixed.extend_from_slice(line);
+            *line_start = line.ends_with(b"\n");
+        }
+        let _ = match self {
+            Echo::Stdout => write_now(&mut io::stdout().lock(), &prefixed),
+            Echo::Stderr => write_now(&mut io::stderr().lock(), &prefixed),
+        };
+    }
+}
+
+fn write_now(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    out.write_all(bytes)?;
+    out.flush()
+}
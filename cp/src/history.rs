@@ -0,0 +1,82 @@
+use crate::{
+    testcase::{os_bytes, os_string},
+    verdict::Verdict,
+};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    str,
+};
+
+/// The verdict every test of a solution got the last time it was run, kept in
+/// `target/cp/<solution>.verdicts` as `<verdict>\t<test>` lines, with the tests
+/// relative to the directory of the run. The paths are kept as they are, even
+/// if they are not UTF-8.
+pub struct History {
+    dir: PathBuf,
+    path: PathBuf,
+    verdicts: BTreeMap<PathBuf, Verdict>,
+}
+
+impl History {
+    /// The history of `solution` run in `dir`, empty if it was never run there.
+    pub fn load(dir: &Path, solution: &Path) -> io::Result<History> {
+        let mut name = solution.file_name().unwrap_or_default().to_os_string();
+        name.push(".verdicts");
+        let path = dir.join("target").join("cp").join(name);
+        let mut verdicts = BTreeMap::new();
+        match fs::read(&path) {
+            Ok(content) => {
+                for line in content.split(|&c| c == b'\n') {
+                    // An unreadable line only loses the verdict of its test
+                    let mut fields = line.splitn(2, |&c| c == b'\t');
+                    let verdict = fields.next().and_then(|v| str::from_utf8(v).ok());
+                    if let (Some(Ok(verdict)), Some(test)) =
+                        (verdict.map(str::parse), fields.next())
+                    {
+                        verdicts.insert(PathBuf::from(os_string(test.to_vec())), verdict);
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(History {
+            dir: dir.to_path_buf(),
+            path,
+            verdicts,
+        })
+    }
+
+    /// Whether `test` failed the last time it was run.
+    pub fn failed(&self, test: &Path) -> bool {
+        self.verdicts
+            .get(self.relative(test))
+            .is_some_and(|verdict| verdict.is_failure())
+    }
+
+    /// Replaces the last verdict of `test`. The ones of the tests that are not
+    /// run again are kept.
+    pub fn record(&mut self, test: &Path, verdict: Verdict) {
+        let test = self.relative(test).to_path_buf();
+        self.verdicts.insert(test, verdict);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = Vec::new();
+        for (test, verdict) in &self.verdicts {
+            content.extend_from_slice(format!("{verdict}\t").as_bytes());
+            content.extend_from_slice(&os_bytes(test.as_os_str()));
+            content.push(b'\n');
+        }
+        fs::write(&self.path, content)
+    }
+
+    fn relative<'a>(&self, test: &'a Path) -> &'a Path {
+        test.strip_prefix(&self.dir).unwrap_or(test)
+    }
+}
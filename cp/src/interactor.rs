@@ -0,0 +1,188 @@
+use crate::{
+    checker,
+    exec::{self, Echo, Execution, Limits, Program},
+    testcase::TestCase,
+    verdict::Judgement,
+};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How long the interactor may keep running after the solution has exited.
+static GRACE_PERIOD: Duration = Duration::from_secs(1);
+
+/// A testlib-compatible interactor, called as `interactor input output [answer]`
+/// with its stdin and stdout wired to the stdout and stdin of the solution.
+#[derive(Clone)]
+pub struct Interactor {
+    program: Program,
+    /// Directory where the exchange of every test is written.
+    transcripts: Option<PathBuf>,
+}
+
+impl Interactor {
+    pub fn new(program: Program, transcripts: Option<PathBuf>) -> Self {
+        Interactor {
+            program,
+            transcripts,
+        }
+    }
+
+    /// Runs `solution` against the interactor. The execution holds what the
+    /// solution printed, the judgement is the one of the interactor.
+    pub fn run(
+        &self,
+        solution: &Program,
+        test: &TestCase,
+        limits: &Limits,
+    ) -> io::Result<(Execution, Judgement)> {
+        let tout = exec::temp_file("tout");
+        let mut interactor = self
+            .program
+            .command()
+            .arg(&test.input)
+            .arg(&tout)
+            .args(&test.answer)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let start = Instant::now();
+        let mut child = match exec::command(solution, limits).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = interactor.kill();
+                let _ = interactor.wait();
+                return Err(e);
+            }
+        };
+        let transcript = Arc::new(Mutex::new(Transcript::default()));
+        let stdout = relay(
+            child.stdout.take(),
+            interactor.stdin.take(),
+            b"> ",
+            Arc::clone(&transcript),
+        );
+        let interactor_stdout = relay(
+            interactor.stdout.take(),
+            child.stdin.take(),
+            b"< ",
+            Arc::clone(&transcript),
+        );
+        let stderr = exec::drain(child.stderr.take(), limits.live.then_some(Echo::Stderr));
+        let interactor_stderr = exec::drain(interactor.stderr.take(), None);
+
+        let (status, timed_out, usage) =
+            exec::wait(&mut child, start, limits.time, limits.cancel.as_deref())?;
+        let elapsed = start.elapsed();
+        let deadline = limits.time.map(|_| elapsed + GRACE_PERIOD);
+        let (interactor_status, _, _) =
+            exec::wait(&mut interactor, start, deadline, limits.cancel.as_deref())?;
+
+        let execution = Execution {
+            stdout: stdout.join().unwrap()?,
+            stderr: stderr.join().unwrap()?,
+            status,
+            elapsed,
+            timed_out,
+            usage,
+        };
+        interactor_stdout.join().unwrap()?;
+        let comment = String::from_utf8_lossy(&interactor_stderr.join().unwrap()?)
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let _ = fs::remove_file(&tout);
+        if let Some(dir) = &self.transcripts {
+            self.write_transcript(dir, &test.input, &transcript.lock().unwrap().buf)?;
+        }
+        let judgement = checker::testlib_judgement(interactor_status.code(), comment);
+        Ok((execution, judgement))
+    }
+
+    fn write_transcript(&self, dir: &Path, input: &Path, transcript: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        let mut name = input.file_name().unwrap_or_default().to_os_string();
+        name.push(".transcript");
+        fs::write(dir.join(name), transcript)
+    }
+}
+
+/// The exchange between solution and interactor, one prefixed line per message:
+/// `> ` for what the solution wrote, `< ` for what the interactor answered.
+#[derive(Default)]
+struct Transcript {
+    buf: Vec<u8>,
+    prefix: Option<&'static [u8]>,
+}
+
+impl Transcript {
+    fn push(&mut self, prefix: &'static [u8], data: &[u8]) {
+        for line in data.split_inclusive(|&c| c == b'\n') {
+            if self.prefix != Some(prefix) {
+                if self.buf.last().is_some_and(|&c| c != b'\n') {
+                    self.buf.push(b'\n');
+                }
+                self.buf.extend_from_slice(prefix);
+            }
+            self.buf.extend_from_slice(line);
+            // After a newline the next line needs its prefix again
+            self.prefix = if line.ends_with(b"\n") {
+                None
+            } else {
+                Some(prefix)
+            };
+        }
+    }
+}
+
+/// Copies everything from `src` to `dst` as soon as it arrives, recording it in
+/// the transcript. Returns what went through. `dst` is closed when `src` is,
+/// and once `dst` is gone the rest of `src` is only recorded.
+fn relay<R, W>(
+    src: Option<R>,
+    mut dst: Option<W>,
+    prefix: &'static [u8],
+    transcript: Arc<Mutex<Transcript>>,
+) -> thread::JoinHandle<io::Result<Vec<u8>>>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut copied = Vec::new();
+        let mut src = match src {
+            Some(src) => src,
+            None => return Ok(copied),
+        };
+        let mut buf = [0; 1 << 13];
+        loop {
+            let n = match src.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            copied.extend_from_slice(&buf[..n]);
+            transcript.lock().unwrap().push(prefix, &buf[..n]);
+            if let Some(pipe) = dst.as_mut() {
+                if pipe
+                    .write_all(&buf[..n])
+                    .and_then(|_| pipe.flush())
+                    .is_err()
+                {
+                    dst = None;
+                }
+            }
+        }
+        Ok(copied)
+    })
+}
@@ -0,0 +1,394 @@
+use crate::{
+    exec::{self, Program},
+    testcase::os_bytes,
+};
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+#[derive(Copy, Clone, Default)]
+pub struct BuildOptions {
+    pub release: bool,
+    pub warnings: bool,
+}
+
+/// The compiler of a language and its flags for every profile. For cargo the
+/// flags are extra arguments of `cargo build`, for Python of `python3`.
+#[derive(Clone)]
+pub struct Toolchain {
+    pub compiler: String,
+    pub debug: Vec<String>,
+    pub release: Vec<String>,
+    pub warnings: Vec<String>,
+    /// After the source, like libraries to link.
+    pub link: Vec<String>,
+}
+
+impl Toolchain {
+    fn new(compiler: &str, debug: &[&str], release: &[&str], warnings: &[&str]) -> Self {
+        let strings = |flags: &[&str]| flags.iter().map(|flag| flag.to_string()).collect();
+        Toolchain {
+            compiler: compiler.to_string(),
+            debug: strings(debug),
+            release: strings(release),
+            warnings: strings(warnings),
+            link: Vec::new(),
+        }
+    }
+
+    fn flags(&self, options: &BuildOptions) -> impl Iterator<Item = &String> {
+        let profile = if options.release {
+            &self.release
+        } else {
+            &self.debug
+        };
+        let warnings = if options.warnings {
+            &self.warnings[..]
+        } else {
+            &[]
+        };
+        profile.iter().chain(warnings)
+    }
+}
+
+/// The toolchains of every language.
+#[derive(Clone)]
+pub struct Languages {
+    pub rust: Toolchain,
+    pub cpp: Toolchain,
+    pub c: Toolchain,
+    pub python: Toolchain,
+}
+
+static GCC_DEBUG: [&str; 4] = ["-O0", "-fsanitize=address,undefined", "-DDEBUG", "-g"];
+static GCC_WARNINGS: [&str; 6] = [
+    "-Wall",
+    "-Wextra",
+    "-Wno-sign-conversion",
+    "-Wshadow",
+    "-D_GLIBCXX_ASSERTIONS",
+    "-fmax-errors=2",
+];
+
+impl Default for Languages {
+    fn default() -> Self {
+        Languages {
+            rust: Toolchain::new("cargo", &[], &[], &[]),
+            cpp: Toolchain::new("g++", &GCC_DEBUG, &["-Ofast"], &GCC_WARNINGS),
+            c: Toolchain {
+                link: vec!["-lm".to_string()],
+                ..Toolchain::new("gcc", &GCC_DEBUG, &["-Ofast"], &GCC_WARNINGS)
+            },
+            python: Toolchain::new("python3", &[], &[], &[]),
+        }
+    }
+}
+
+/// How to build and run the sources of a language.
+pub trait Backend {
+    fn toolchain(&self) -> &Toolchain;
+
+    /// Builds `source`, returning what `run` needs, usually a binary.
+    fn build(&self, source: &Path, options: &BuildOptions) -> io::Result<PathBuf>;
+
+    /// How to run what `build` returned.
+    fn run(&self, artifact: PathBuf, _options: &BuildOptions) -> Program {
+        Program::new(artifact)
+    }
+
+    /// What `build` reads, to watch for changes.
+    fn sources(&self, source: &Path) -> Vec<PathBuf> {
+        vec![source.to_path_buf()]
+    }
+
+    /// Whether the build runs under sanitizers, which reserve terabytes of
+    /// address space, see `Limits::enforce_memory`.
+    fn sanitized(&self, _options: &BuildOptions) -> bool {
+        false
+    }
+}
+
+/// The binaries of the cargo project in the current directory: `sol`,
+/// `sol.rs` and `src/bin/sol.rs` all are `cargo build --bin sol`.
+pub struct Cargo<'a>(&'a Toolchain);
+
+/// `g++` for C++, `gcc` for C. The binaries are cached in `target/cp/build/`,
+/// named after a hash of the source, the headers it includes with quotes, the
+/// compiler and the flags, so unchanged sources are not compiled again.
+pub struct Gcc<'a>(&'a Toolchain);
+
+/// Run by `python3`, nothing to build.
+pub struct Python<'a>(&'a Toolchain);
+
+/// The backend of `source`, from its extension. A path without one that is not
+/// a file is taken as the name of a cargo binary.
+pub fn backend<'a>(source: &Path, languages: &'a Languages) -> Option<Box<dyn Backend + 'a>> {
+    match source.extension().and_then(|ext| ext.to_str()) {
+        Some("rs") => Some(Box::new(Cargo(&languages.rust))),
+        Some("cpp" | "cc" | "cxx") => Some(Box::new(Gcc(&languages.cpp))),
+        Some("c") => Some(Box::new(Gcc(&languages.c))),
+        Some("py") => Some(Box::new(Python(&languages.python))),
+        None if !source.is_file() => Some(Box::new(Cargo(&languages.rust))),
+        _ => None,
+    }
+}
+
+/// A built source, ready to run.
+pub struct Built {
+    pub program: Program,
+    pub sanitized: bool,
+    pub info: BuildInfo,
+}
+
+/// What a source was built from and how, for reports.
+#[derive(Clone, Default)]
+pub struct BuildInfo {
+    pub source: PathBuf,
+    /// `None` for executables.
+    pub compiler: Option<String>,
+    pub flags: Vec<String>,
+    pub release: bool,
+    /// Of every file the build reads, see `source_hash`.
+    pub source_hash: u64,
+}
+
+/// Builds `source` with the backend of its language. Anything else is taken as
+/// an executable.
+pub fn build(source: &Path, options: &BuildOptions, languages: &Languages) -> io::Result<Built> {
+    let mut info = BuildInfo {
+        source: source.to_path_buf(),
+        release: options.release,
+        ..BuildInfo::default()
+    };
+    let backend = match backend(source, languages) {
+        Some(backend) => backend,
+        None => {
+            info.source_hash = source_hash(&[source.to_path_buf()])?;
+            return Ok(Built {
+                program: Program::new(fs::canonicalize(source)?),
+                sanitized: false,
+                info,
+            });
+        }
+    };
+    let artifact = backend.build(source, options)?;
+    let toolchain = backend.toolchain();
+    info.compiler = Some(toolchain.compiler.clone());
+    info.flags = toolchain
+        .flags(options)
+        .chain(&toolchain.link)
+        .cloned()
+        .collect();
+    info.source_hash = source_hash(&backend.sources(source))?;
+    Ok(Built {
+        program: backend.run(artifact, options),
+        sanitized: backend.sanitized(options),
+        info,
+    })
+}
+
+/// FNV-1a, which unlike `DefaultHasher` stays the same across versions of Rust,
+/// so that cached binaries survive an update and the reports of different runs
+/// can be compared.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        StableHasher(0xcbf29ce484222325)
+    }
+
+    /// Adds `bytes`, after their length so that consecutive parts can't run
+    /// into each other.
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The hash of the names and contents of the files in `paths`, in sorted
+/// order, see `StableHasher`.
+fn source_hash(paths: &[PathBuf]) -> io::Result<u64> {
+    fn walk(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        if path.is_dir() {
+            for entry in fs::read_dir(path)? {
+                walk(&entry?.path(), files)?;
+            }
+        } else if path.is_file() {
+            files.push(path.to_path_buf());
+        }
+        Ok(())
+    }
+    let mut all = Vec::new();
+    for path in paths {
+        walk(path, &mut all)?;
+    }
+    all.sort();
+    let mut hasher = StableHasher::new();
+    for file in &all {
+        // Only the names of several files tell them apart
+        if all.len() > 1 {
+            hasher.write(&os_bytes(file.as_os_str()));
+        }
+        hasher.write(&fs::read(file)?);
+    }
+    Ok(hasher.finish())
+}
+
+/// The files and folders `source` is built from: the whole cargo project for a
+/// cargo binary.
+pub fn sources(source: &Path, languages: &Languages) -> Vec<PathBuf> {
+    match backend(source, languages) {
+        Some(backend) => backend.sources(source),
+        None => vec![source.to_path_buf()],
+    }
+}
+
+fn check_status(command: &mut Command, source: &Path) -> io::Result<()> {
+    if command.status()?.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!(
+            "Can't build {}",
+            source.display()
+        )))
+    }
+}
+
+impl Backend for Cargo<'_> {
+    fn toolchain(&self) -> &Toolchain {
+        self.0
+    }
+
+    fn build(&self, source: &Path, options: &BuildOptions) -> io::Result<PathBuf> {
+        let name = source
+            .file_stem()
+            .ok_or_else(|| io::Error::other(format!("No binary in {}", source.display())))?;
+        let mut command = Command::new(&self.0.compiler);
+        command.arg("build").arg("--bin").arg(name);
+        if options.release {
+            command.arg("--release");
+        }
+        command.args(self.0.flags(options));
+        check_status(&mut command, source)?;
+        let mut binary = name.to_os_string();
+        binary.push(env::consts::EXE_SUFFIX);
+        Ok(env::current_dir()?
+            .join("target")
+            .join(if options.release { "release" } else { "debug" })
+            .join(binary))
+    }
+
+    fn sources(&self, source: &Path) -> Vec<PathBuf> {
+        let mut sources = vec![PathBuf::from("src"), PathBuf::from("Cargo.toml")];
+        if source.is_file() {
+            sources.push(source.to_path_buf());
+        }
+        sources
+    }
+}
+
+impl Backend for Gcc<'_> {
+    fn toolchain(&self) -> &Toolchain {
+        self.0
+    }
+
+    fn build(&self, source: &Path, options: &BuildOptions) -> io::Result<PathBuf> {
+        let flags: Vec<_> = self.0.flags(options).collect();
+        let mut hasher = StableHasher::new();
+        hasher.write(&source_hash(&self.sources(source))?.to_le_bytes());
+        hasher.write(self.0.compiler.as_bytes());
+        // Where the flags end, as the link flags go after the source
+        hasher.write(&(flags.len() as u64).to_le_bytes());
+        for flag in flags.iter().copied().chain(&self.0.link) {
+            hasher.write(flag.as_bytes());
+        }
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        let cache = env::current_dir()?.join("target").join("cp").join("build");
+        let binary = cache.join(format!(
+            "{stem}-{:016x}{}",
+            hasher.finish(),
+            env::consts::EXE_SUFFIX
+        ));
+        if binary.is_file() {
+            return Ok(binary);
+        }
+        fs::create_dir_all(&cache)?;
+        // Built under a name of its own and moved in place when complete, so
+        // that other runs never see a binary being written
+        let partial = cache.join(exec::unique_name("bin"));
+        let mut command = Command::new(&self.0.compiler);
+        command.args(flags);
+        command.arg(source).arg("-o").arg(&partial);
+        command.args(&self.0.link);
+        if let Err(e) = check_status(&mut command, source) {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+        fs::rename(&partial, &binary)?;
+        Ok(binary)
+    }
+
+    fn sources(&self, source: &Path) -> Vec<PathBuf> {
+        let mut sources = vec![source.to_path_buf()];
+        local_includes(source, &mut sources);
+        sources
+    }
+
+    fn sanitized(&self, options: &BuildOptions) -> bool {
+        self.0
+            .flags(options)
+            .any(|flag| flag.starts_with("-fsanitize=address"))
+    }
+}
+
+/// Adds to `files` the headers `source` includes with `#include "..."` that are
+/// next to it, and the ones they include. System headers and the folders of
+/// `-I` are not followed.
+fn local_includes(source: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(content) = fs::read(source) else {
+        return;
+    };
+    let dir = source.parent().unwrap_or(Path::new(""));
+    for line in String::from_utf8_lossy(&content).lines() {
+        let header = line
+            .trim_start()
+            .strip_prefix('#')
+            .map(str::trim_start)
+            .and_then(|line| line.strip_prefix("include"))
+            .map(str::trim_start)
+            .and_then(|line| line.strip_prefix('"'))
+            .and_then(|line| line.split_once('"'))
+            .map(|(header, _)| dir.join(header));
+        if let Some(header) = header {
+            if header.is_file() && !files.contains(&header) {
+                files.push(header.clone());
+                local_includes(&header, files);
+            }
+        }
+    }
+}
+
+impl Backend for Python<'_> {
+    fn toolchain(&self) -> &Toolchain {
+        self.0
+    }
+
+    fn build(&self, source: &Path, _options: &BuildOptions) -> io::Result<PathBuf> {
+        fs::canonicalize(source)
+    }
+
+    fn run(&self, artifact: PathBuf, options: &BuildOptions) -> Program {
+        let mut program = Program::new(&self.0.compiler);
+        for flag in self.0.flags(options) {
+            program = program.arg(flag);
+        }
+        program.arg(artifact)
+    }
+}
@@ -1 +1,16 @@
+pub mod checker;
+pub mod compare;
+pub mod config;
+pub mod diff;
+pub mod exec;
+pub mod history;
+pub mod interactor;
+pub mod lang;
+pub mod output;
+pub mod report;
+pub mod shrink;
+pub mod stress;
+pub mod testcase;
 pub mod threadpool;
+pub mod verdict;
+pub mod watch;
@@ -0,0 +1,332 @@
+use crate::testcase::{os_bytes, os_string, TestCase};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The file listing what was written in an output folder, one path relative to
+/// it per line. A folder without one was not made by us and is never wiped
+/// unless forced.
+pub const MANIFEST: &str = ".cp-manifest";
+
+/// A folder the outputs of a run are written in, keeping track of every file
+/// written so that the next run only removes those.
+pub struct OutputDir {
+    dir: PathBuf,
+    manifest: fs::File,
+}
+
+impl OutputDir {
+    /// Empties `dir` of the files of the previous run and starts a new one in
+    /// it. Fails if `dir` has something not written by us, unless `force`, in
+    /// which case it is wiped.
+    pub fn create(dir: &Path, force: bool) -> io::Result<OutputDir> {
+        if claim(dir, force)? {
+            empty(dir)?;
+        }
+        OutputDir::open(dir.to_path_buf(), true)
+    }
+
+    /// Starts a new run in a folder of `dir` named after the current time,
+    /// keeping the previous ones. `dir` is claimed like in `create` but never
+    /// emptied.
+    pub fn create_run(dir: &Path, force: bool) -> io::Result<OutputDir> {
+        claim(dir, force)?;
+        let mut parent = OutputDir::open(dir.to_path_buf(), false)?;
+        let stamp = timestamp(SystemTime::now());
+        let mut name = PathBuf::from(&stamp);
+        // Two runs in the same second
+        let mut n = 1;
+        while dir.join(&name).exists() {
+            n += 1;
+            name = PathBuf::from(format!("{stamp}-{n}"));
+        }
+        fs::create_dir(dir.join(&name))?;
+        parent.record(&name)?;
+        OutputDir::open(dir.join(name), true)
+    }
+
+    fn open(dir: PathBuf, truncate: bool) -> io::Result<OutputDir> {
+        fs::create_dir_all(&dir)?;
+        let manifest = OpenOptions::new()
+            .create(true)
+            .append(!truncate)
+            .write(true)
+            .truncate(truncate)
+            .open(dir.join(MANIFEST))?;
+        Ok(OutputDir { dir, manifest })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Writes `content` to `name`, relative to the folder, creating the
+    /// folders it is in.
+    pub fn write(&mut self, name: &Path, content: &[u8]) -> io::Result<()> {
+        let path = self.dir.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Listed first, so that it is removed even if the run stops right after
+        self.record(name)?;
+        fs::write(path, content)
+    }
+
+    fn record(&mut self, name: &Path) -> io::Result<()> {
+        let mut line = os_bytes(name.as_os_str()).into_owned();
+        line.push(b'\n');
+        self.manifest.write_all(&line)
+    }
+}
+
+/// Refuses an output folder that holds what must never be removed: the
+/// current directory `current_dir`, one of its parents or one of the test
+/// `folders`. Even `--force` can't replace those.
+pub fn check(dir: &Path, current_dir: &Path, folders: &[PathBuf]) -> io::Result<()> {
+    let dir = resolve(dir);
+    let same = |other: &Path| fs::canonicalize(other).is_ok_and(|other| other == dir);
+    let ancestor = fs::canonicalize(current_dir)?.starts_with(&dir);
+    if ancestor || folders.iter().any(|folder| same(folder)) {
+        return Err(io::Error::other(format!(
+            "{} can't be the output folder, it has the tests or the sources",
+            dir.display()
+        )));
+    }
+    Ok(())
+}
+
+/// `path` without symbolic links, `.` and `..`, even where it doesn't exist
+/// yet: `missing/..` is the folder `missing` would be in.
+fn resolve(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved = fs::canonicalize(&resolved).unwrap_or(resolved);
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    fs::canonicalize(&resolved).unwrap_or(resolved)
+}
+
+/// The names of the outputs of `tests`, found in `dir`: the path of the input
+/// in its test folder with an `.out` extension (`input/07.in` -> `07.out`). If
+/// two of them end up the same, the test folders are kept and `.out` is added
+/// to the whole name instead (`input/07.in` -> `input/07.in.out`).
+pub fn names(tests: &[TestCase], dir: &Path) -> Vec<PathBuf> {
+    let relative = |test: &TestCase| {
+        test.input
+            .strip_prefix(dir)
+            .unwrap_or(&test.input)
+            .to_path_buf()
+    };
+    let names: Vec<_> = tests
+        .iter()
+        .map(|test| {
+            let relative = relative(test);
+            let mut components = relative.components();
+            // Tests are either in `dir` or in one of its test folders
+            if relative.components().count() > 1 {
+                components.next();
+            }
+            components.as_path().with_extension("out")
+        })
+        .collect();
+    if names.iter().collect::<HashSet<_>>().len() == names.len() {
+        return names;
+    }
+    tests
+        .iter()
+        .map(|test| {
+            let mut name = OsString::from(relative(test));
+            name.push(".out");
+            PathBuf::from(name)
+        })
+        .collect()
+}
+
+/// Whether `dir` is ours: missing, empty or with a manifest. With `force` it
+/// is made ours by wiping it.
+fn claim(dir: &Path, force: bool) -> io::Result<bool> {
+    let ours = match fs::read_dir(dir) {
+        Ok(mut entries) => {
+            dir.is_dir() && (dir.join(MANIFEST).is_file() || entries.next().is_none())
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        // Not a folder
+        Err(_) => false,
+    };
+    if ours {
+        return Ok(true);
+    }
+    if !force {
+        return Err(io::Error::other(format!(
+            "{} was not created by cp-io, use --force to replace it",
+            dir.display()
+        )));
+    }
+    if dir.is_dir() {
+        fs::remove_dir_all(dir)?;
+    } else {
+        fs::remove_file(dir)?;
+    }
+    Ok(false)
+}
+
+/// Removes what the manifest of `dir` lists, and the manifest.
+fn empty(dir: &Path) -> io::Result<()> {
+    for name in written(dir)?.iter().rev() {
+        remove(dir, name)?;
+    }
+    match fs::remove_file(dir.join(MANIFEST)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// The files listed in the manifest of `dir`.
+fn written(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let content = match fs::read(dir.join(MANIFEST)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    Ok(content
+        .split(|&c| c == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| PathBuf::from(os_string(line.to_vec())))
+        .collect())
+}
+
+/// Removes `name` from `dir`, a file or the folder of a run, then the folders
+/// it was in if they are left empty. Nothing outside of `dir` is touched,
+/// whatever the manifest says.
+fn remove(dir: &Path, name: &Path) -> io::Result<()> {
+    if name.is_absolute() || name.components().any(|c| c.as_os_str() == "..") {
+        return Ok(());
+    }
+    let path = dir.join(name);
+    // The folder of a run goes too once emptied, a file only leaves its folders
+    let skip = if path.is_dir() {
+        empty(&path)?;
+        0
+    } else {
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => 1,
+        }
+    };
+    for folder in path.ancestors().skip(skip) {
+        if folder == dir || fs::remove_dir(folder).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// `2024-03-09T14-05-31`, in UTC.
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = (secs / 86400, secs % 86400);
+    // Days to a civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}-{:02}-{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec;
+
+    /// A new empty folder, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = exec::temp_file("dir");
+            fs::create_dir(&dir).unwrap();
+            TempDir(fs::canonicalize(dir).unwrap())
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn folders_without_manifest_are_refused() {
+        let temp = TempDir::new();
+        let dir = temp.0.join("output");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        assert!(OutputDir::create(&dir, false).is_err());
+        assert!(OutputDir::create_run(&dir, false).is_err());
+        assert!(dir.join("notes.txt").is_file());
+    }
+
+    #[test]
+    fn only_the_files_of_the_manifest_are_removed() {
+        let temp = TempDir::new();
+        let dir = temp.0.join("output");
+        let mut output = OutputDir::create(&dir, false).unwrap();
+        output.write(Path::new("a/1.out"), b"1").unwrap();
+        drop(output);
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        OutputDir::create(&dir, false).unwrap();
+        assert!(!dir.join("a").exists());
+        assert!(dir.join("notes.txt").is_file());
+    }
+
+    #[test]
+    fn manifest_entries_outside_of_the_folder_are_ignored() {
+        let temp = TempDir::new();
+        let dir = temp.0.join("output");
+        fs::create_dir(&dir).unwrap();
+        fs::write(temp.0.join("x"), "").unwrap();
+        let absolute = temp.0.join("x");
+        let manifest = format!("../x\n{}\n", absolute.display());
+        fs::write(dir.join(MANIFEST), manifest).unwrap();
+        OutputDir::create(&dir, false).unwrap();
+        assert!(temp.0.join("x").is_file());
+    }
+
+    #[test]
+    fn sources_and_tests_are_never_the_output_folder() {
+        let temp = TempDir::new();
+        let current = temp.0.join("problem");
+        let tests = current.join("tests");
+        fs::create_dir_all(&tests).unwrap();
+        let folders = [tests.clone()];
+        assert!(check(&current.join("."), &current, &folders).is_err());
+        assert!(check(&current.join(".."), &current, &folders).is_err());
+        assert!(check(&current.join("missing/.."), &current, &folders).is_err());
+        assert!(check(&tests, &current, &folders).is_err());
+        assert!(check(&current.join("output"), &current, &folders).is_ok());
+    }
+}
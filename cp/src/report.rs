@@ -0,0 +1,284 @@
+use crate::{
+    exec::Execution,
+    lang::BuildInfo,
+    verdict::{Judgement, Verdict},
+};
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// The formats of `--report`.
+#[derive(Copy, Clone, Debug)]
+pub enum Format {
+    Json,
+    /// JUnit XML, as read by most CI servers and dashboards.
+    Junit,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Format::Json),
+            "junit" => Ok(Format::Junit),
+            _ => Err(format!(
+                "unknown report format `{s}`, try `json` or `junit`"
+            )),
+        }
+    }
+}
+
+/// How a test went.
+pub struct TestResult {
+    pub name: String,
+    pub input: PathBuf,
+    pub verdict: Verdict,
+    pub points: Option<f64>,
+    /// What the checker or the runtime had to say.
+    pub comment: Option<String>,
+    pub wall: Duration,
+    pub cpu: Option<Duration>,
+    /// Peak memory in bytes.
+    pub memory: Option<u64>,
+    pub exit_status: String,
+}
+
+impl TestResult {
+    pub fn new(name: String, input: &Path, execution: &Execution, judgement: &Judgement) -> Self {
+        TestResult {
+            name,
+            input: input.to_path_buf(),
+            verdict: judgement.verdict,
+            points: judgement.points,
+            comment: judgement.comment.clone(),
+            wall: execution.elapsed,
+            cpu: execution.usage.map(|usage| usage.cpu()),
+            memory: execution.usage.map(|usage| usage.max_rss),
+            exit_status: execution.exit_status(),
+        }
+    }
+}
+
+/// The tests of a run with how the solution was built, written for scripts
+/// and dashboards.
+pub struct RunReport {
+    pub build: BuildInfo,
+    pub tests: Vec<TestResult>,
+}
+
+impl RunReport {
+    pub fn write(&self, format: Format, path: &Path) -> io::Result<()> {
+        let content = match format {
+            Format::Json => self.json(),
+            Format::Junit => self.junit(),
+        };
+        fs::write(path, content)
+    }
+
+    /// `None` for executables, which are not built.
+    fn profile(&self) -> Option<String> {
+        let profile = if self.build.release {
+            "release"
+        } else {
+            "debug"
+        };
+        self.build.compiler.as_ref().map(|_| profile.to_string())
+    }
+
+    fn count(&self, f: impl Fn(Verdict) -> bool) -> usize {
+        self.tests.iter().filter(|test| f(test.verdict)).count()
+    }
+
+    fn json(&self) -> String {
+        let build = &self.build;
+        let mut out = String::from("{\n  \"build\": {\n");
+        let flags: Vec<_> = build.flags.iter().map(|flag| json(flag)).collect();
+        let _ = writeln!(
+            out,
+            "    \"source\": {},",
+            json(&build.source.to_string_lossy())
+        );
+        let _ = writeln!(out, "    \"compiler\": {},", json_or_null(&build.compiler));
+        let _ = writeln!(out, "    \"flags\": [{}],", flags.join(", "));
+        let _ = writeln!(out, "    \"profile\": {},", json_or_null(&self.profile()));
+        let _ = writeln!(out, "    \"source_hash\": \"{:016x}\"", build.source_hash);
+        out.push_str("  },\n");
+        let _ = writeln!(
+            out,
+            "  \"summary\": {{\"tests\": {}, \"passed\": {}, \"failed\": {}}},",
+            self.tests.len(),
+            self.count(|v| v == Verdict::Accepted),
+            self.count(Verdict::is_failure)
+        );
+        out.push_str("  \"tests\": [");
+        for (i, test) in self.tests.iter().enumerate() {
+            out.push_str(if i == 0 { "\n    " } else { ",\n    " });
+            let _ = write!(
+                out,
+                "{{\"name\": {}, \"input\": {}, \"verdict\": {}, \"points\": {}, \
+                 \"comment\": {}, \"wall_ms\": {}, \"cpu_ms\": {}, \"memory_bytes\": {}, \
+                 \"exit_status\": {}}}",
+                json(&test.name),
+                json(&test.input.to_string_lossy()),
+                json(&test.verdict.to_string()),
+                test.points
+                    .filter(|p| p.is_finite())
+                    .map_or("null".to_string(), |p| p.to_string()),
+                json_or_null(&test.comment),
+                millis(test.wall),
+                test.cpu.map_or("null".to_string(), millis),
+                test.memory.map_or("null".to_string(), |m| m.to_string()),
+                json(&test.exit_status)
+            );
+        }
+        out.push_str(if self.tests.is_empty() {
+            "]\n}\n"
+        } else {
+            "\n  ]\n}\n"
+        });
+        out
+    }
+
+    /// Wrong answers are failures, crashes and limits errors, and tests
+    /// without an answer are skipped.
+    fn junit(&self) -> String {
+        let build = &self.build;
+        let suite = xml(&build.source.to_string_lossy());
+        let time: Duration = self.tests.iter().map(|test| test.wall).sum();
+        let errors = self.count(|v| {
+            matches!(
+                v,
+                Verdict::TimeLimitExceeded
+                    | Verdict::MemoryLimitExceeded
+                    | Verdict::RuntimeError
+                    | Verdict::CheckerFailed
+            )
+        });
+        let failures = self.count(Verdict::is_failure) - errors;
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{suite}\" tests=\"{}\" failures=\"{failures}\" \
+             errors=\"{errors}\" skipped=\"{}\" time=\"{:.6}\">",
+            self.tests.len(),
+            self.count(|v| v == Verdict::NoReference),
+            time.as_secs_f64()
+        );
+        out.push_str("    <properties>\n");
+        let properties = [
+            ("compiler", build.compiler.clone().unwrap_or_default()),
+            ("flags", build.flags.join(" ")),
+            ("profile", self.profile().unwrap_or_default()),
+            ("source_hash", format!("{:016x}", build.source_hash)),
+        ];
+        for (name, value) in properties {
+            let _ = writeln!(
+                out,
+                "      <property name=\"{name}\" value=\"{}\"/>",
+                xml(&value)
+            );
+        }
+        out.push_str("    </properties>\n");
+        for test in &self.tests {
+            let _ = writeln!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{suite}\" file=\"{}\" time=\"{:.6}\">",
+                xml(&test.name),
+                xml(&test.input.to_string_lossy()),
+                test.wall.as_secs_f64()
+            );
+            let message = match &test.comment {
+                Some(comment) => format!("{} ({comment})", test.verdict),
+                None => test.verdict.to_string(),
+            };
+            let element = match test.verdict {
+                Verdict::Accepted => None,
+                Verdict::NoReference => Some("skipped"),
+                Verdict::WrongAnswer | Verdict::PresentationError | Verdict::Partial => {
+                    Some("failure")
+                }
+                _ => Some("error"),
+            };
+            if let Some(element) = element {
+                let _ = writeln!(
+                    out,
+                    "      <{element} type=\"{}\" message=\"{}\"/>",
+                    test.verdict,
+                    xml(&message)
+                );
+            }
+            out.push_str("      <properties>\n");
+            let properties = [
+                ("verdict", Some(test.verdict.to_string())),
+                ("points", test.points.map(|p| p.to_string())),
+                ("cpu_ms", test.cpu.map(millis)),
+                ("memory_bytes", test.memory.map(|m| m.to_string())),
+                ("exit_status", Some(test.exit_status.clone())),
+            ];
+            for (name, value) in properties {
+                if let Some(value) = value {
+                    let _ = writeln!(
+                        out,
+                        "        <property name=\"{name}\" value=\"{}\"/>",
+                        xml(&value)
+                    );
+                }
+            }
+            out.push_str("      </properties>\n    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n</testsuites>\n");
+        out
+    }
+}
+
+fn millis(duration: Duration) -> String {
+    format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+/// `s` as a JSON string, quoted.
+fn json(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn json_or_null(s: &Option<String>) -> String {
+    s.as_deref().map_or("null".to_string(), json)
+}
+
+/// `s` escaped for an XML attribute. Control characters are not allowed in XML
+/// 1.0 at all, they are dropped.
+fn xml(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            '\t' => out.push_str("&#9;"),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,319 @@
+use std::{io, str};
+
+/// Shrinks a failing input to a locally minimal one for which `still_fails`
+/// holds, with token-level heuristics: dropping lines, dropping elements of a
+/// line and making numbers smaller. When lines or elements are dropped, the
+/// count that comes before them is fixed up, if there is one. The tokens are
+/// kept as bytes, the input need not be UTF-8.
+pub fn shrink<F>(input: &[u8], mut still_fails: F) -> io::Result<Vec<u8>>
+where
+    F: FnMut(&[u8]) -> io::Result<bool>,
+{
+    let mut best = Tokens::parse(input);
+    loop {
+        let mut shrunk = false;
+        for candidate in best.candidates() {
+            if still_fails(&candidate.render())? {
+                best = candidate;
+                shrunk = true;
+                break;
+            }
+        }
+        if !shrunk {
+            return Ok(best.render());
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Tokens {
+    lines: Vec<Vec<Vec<u8>>>,
+}
+
+impl Tokens {
+    fn parse(input: &[u8]) -> Self {
+        let lines = input
+            .split(|&c| c == b'\n')
+            .map(|line| {
+                line.split(u8::is_ascii_whitespace)
+                    .filter(|token| !token.is_empty())
+                    .map(<[u8]>::to_vec)
+                    .collect()
+            })
+            .filter(|line: &Vec<Vec<u8>>| !line.is_empty())
+            .collect();
+        Tokens { lines }
+    }
+
+    fn render(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for line in &self.lines {
+            out.extend_from_slice(&line.join(&b' '));
+            out.push(b'\n');
+        }
+        out
+    }
+
+    /// Smaller variants of the input, the ones that remove most first.
+    fn candidates(&self) -> Vec<Tokens> {
+        let mut candidates = Vec::new();
+        self.drop_lines(&mut candidates);
+        self.drop_elements(&mut candidates);
+        self.shrink_numbers(&mut candidates);
+        candidates
+    }
+
+    /// Drops chunks of the runs of lines with the same number of tokens, which
+    /// usually are the rows of a list introduced by a count.
+    fn drop_lines(&self, candidates: &mut Vec<Tokens>) {
+        let mut start = 0;
+        while start < self.lines.len() {
+            let width = self.lines[start].len();
+            let mut end = start;
+            while end < self.lines.len() && self.lines[end].len() == width {
+                end += 1;
+            }
+            // The first line may be the count of the others (`2\n5\n7`)
+            let first = start + self.holds_count(start) as usize;
+            let run = end - first;
+            // Without its counts the rest of the input can't be read anymore
+            if (first..end).any(|i| self.holds_count(i)) {
+                start = end;
+                continue;
+            }
+            for chunk in chunk_sizes(run) {
+                for from in (first..=end - chunk).step_by(chunk) {
+                    let mut candidate = self.clone();
+                    candidate.lines.drain(from..from + chunk);
+                    if run == 1 && self.counted(first) {
+                        // The count was of its elements (`3\n7 8 9`), none are left
+                        candidate.fix_count(first, 0, width, width);
+                    } else {
+                        candidate.fix_count(first, 0, run, chunk);
+                    }
+                    candidates.push(candidate);
+                }
+            }
+            start = end;
+        }
+    }
+
+    /// Drops chunks of the elements of every line but the ones holding counts.
+    /// A first token counting the others (`3 a b c`) is kept and fixed up,
+    /// unless the line before already counts them all (`4\n3 a b c`).
+    fn drop_elements(&self, candidates: &mut Vec<Tokens>) {
+        for (i, line) in self.lines.iter().enumerate() {
+            let inline = number(&line[0]).is_some_and(|value| self.is_count(i, 0, value))
+                && line[0] == token(line.len() - 1);
+            if !inline && self.holds_count(i) {
+                continue;
+            }
+            let first = inline as usize;
+            let len = line.len() - first;
+            for chunk in chunk_sizes(len) {
+                for from in (first..=line.len() - chunk).step_by(chunk) {
+                    let mut candidate = self.clone();
+                    candidate.lines[i].drain(from..from + chunk);
+                    if inline {
+                        candidate.lines[i][0] = token(len - chunk);
+                    } else {
+                        candidate.fix_count(i, 0, len, chunk);
+                    }
+                    // Whole lines are dropped by `drop_lines`, with their count
+                    if !candidate.lines[i].is_empty() {
+                        candidates.push(candidate);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Makes every number that is not a count smaller.
+    fn shrink_numbers(&self, candidates: &mut Vec<Tokens>) {
+        for (i, line) in self.lines.iter().enumerate() {
+            for (j, token) in line.iter().enumerate() {
+                let value = match number(token) {
+                    Some(value) => value,
+                    None => continue,
+                };
+                if self.is_count(i, j, value) {
+                    continue;
+                }
+                let mut smaller = vec![0, value / 2, value - value.signum()];
+                smaller.retain(|v| v.unsigned_abs() < value.unsigned_abs());
+                smaller.dedup();
+                for v in smaller {
+                    let mut candidate = self.clone();
+                    candidate.lines[i][j] = self::token(v);
+                    candidates.push(candidate);
+                }
+            }
+        }
+    }
+
+    /// Decrements by `chunk` the nearest count before line `line` that is
+    /// equal to `len`, looking at the tokens before `column` on that line too.
+    fn fix_count(&mut self, line: usize, column: usize, len: usize, chunk: usize) {
+        let width = self.lines.get(line).map_or(0, Vec::len);
+        let mut positions = (0..column.min(width)).rev().map(|j| (line, j)).chain(
+            (0..line)
+                .rev()
+                .flat_map(|i| (0..self.lines[i].len()).rev().map(move |j| (i, j))),
+        );
+        if let Some((i, j)) = positions.find(|&(i, j)| self.lines[i][j] == token(len)) {
+            self.lines[i][j] = token(len - chunk);
+        }
+    }
+
+    /// Whether line `line` is a list counted by the line before it.
+    fn counted(&self, line: usize) -> bool {
+        let width = self.lines[line].len() as i64;
+        line > 0
+            && (0..self.lines[line - 1].len()).any(|j| {
+                number(&self.lines[line - 1][j]) == Some(width) && self.is_count(line - 1, j, width)
+            })
+    }
+
+    fn holds_count(&self, line: usize) -> bool {
+        self.lines[line]
+            .iter()
+            .enumerate()
+            .any(|(j, token)| number(token).is_some_and(|value| self.is_count(line, j, value)))
+    }
+
+    /// Whether `value`, the token `column` of line `line`, looks like the length
+    /// of a list that follows it: the rest of its line, the next line or the run
+    /// of lines after it. Only the first line of a run can count the rest of
+    /// it, the others are its elements.
+    fn is_count(&self, line: usize, column: usize, value: i64) -> bool {
+        if value <= 0 {
+            return false;
+        }
+        let value = value as usize;
+        let width = self.lines[line].len();
+        if column == 0 && width == value + 1 && !self.counted(line) {
+            return true;
+        }
+        let mut rest = self.lines[line + 1..].iter().peekable();
+        match rest.peek().map(|next| next.len()) {
+            Some(next_width) if next_width != width => {
+                next_width == value
+                    || rest.take_while(|next| next.len() == next_width).count() == value
+            }
+            Some(_) => {
+                let starts_run = line == 0 || self.lines[line - 1].len() != width;
+                starts_run && rest.take_while(|next| next.len() == width).count() == value
+            }
+            None => false,
+        }
+    }
+}
+
+fn number(token: &[u8]) -> Option<i64> {
+    str::from_utf8(token).ok()?.parse().ok()
+}
+
+fn token(value: impl ToString) -> Vec<u8> {
+    value.to_string().into_bytes()
+}
+
+/// `len / 2, len / 4, ..., 1`, the sizes of the chunks to drop.
+fn chunk_sizes(len: usize) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut chunk = len / 2;
+    while chunk > 0 {
+        sizes.push(chunk);
+        chunk /= 2;
+    }
+    if len == 1 {
+        sizes.push(1);
+    }
+    sizes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(input: &str) -> Vec<String> {
+        Tokens::parse(input.as_bytes())
+            .candidates()
+            .iter()
+            .map(|candidate| String::from_utf8(candidate.render()).unwrap())
+            .collect()
+    }
+
+    fn is_count(input: &str, line: usize, column: usize) -> bool {
+        let tokens = Tokens::parse(input.as_bytes());
+        let value = number(&tokens.lines[line][column]).unwrap();
+        tokens.is_count(line, column, value)
+    }
+
+    #[test]
+    fn counts() {
+        // The rest of the line, the next line and the run of lines after it
+        assert!(is_count("3 4 5 6", 0, 0));
+        assert!(is_count("3\n4 5 6", 0, 0));
+        assert!(is_count("2\n4 5\n6 7", 0, 0));
+        // Lists of one element as wide as their count
+        assert!(is_count("1\n-1", 0, 0));
+        assert!(is_count("3\n4\n5\n6", 0, 0));
+        // Elements of a run don't count the rest of it
+        assert!(!is_count("3\n2\n5\n6", 1, 0));
+        assert!(!is_count("2\n4 5 6", 0, 0));
+        assert!(!is_count("0\n", 0, 0));
+        assert!(!is_count("5", 0, 0));
+    }
+
+    #[test]
+    fn one_element_list_keeps_its_count() {
+        let candidates = rendered("1\n-1\n");
+        assert!(candidates.contains(&"0\n".to_string()));
+        assert!(!candidates.contains(&"0\n-1\n".to_string()));
+        assert!(!candidates.contains(&"-1\n".to_string()));
+    }
+
+    #[test]
+    fn dropped_lines_fix_their_count() {
+        let candidates = rendered("4\n1\n2\n3\n4\n");
+        assert!(candidates.contains(&"2\n3\n4\n".to_string()));
+        assert!(candidates.contains(&"3\n1\n3\n4\n".to_string()));
+        for candidate in &candidates {
+            let lines: Vec<_> = candidate.lines().collect();
+            assert_eq!(lines[0].parse::<usize>().unwrap(), lines.len() - 1);
+        }
+    }
+
+    #[test]
+    fn dropped_elements_fix_their_count() {
+        let candidates = rendered("3\n7 8 9\n");
+        assert!(candidates.contains(&"2\n8 9\n".to_string()));
+        assert!(candidates.contains(&"0\n".to_string()));
+        assert!(!candidates.contains(&"3\n".to_string()));
+        assert!(candidates.contains(&"2\n7 9\n".to_string()));
+        let candidates = rendered("3 7 8 9\n");
+        assert!(candidates.contains(&"2 7 9\n".to_string()));
+        assert!(!candidates
+            .iter()
+            .any(|c| c.starts_with("3 ") && c.len() < 8));
+    }
+
+    #[test]
+    fn numbers_get_smaller_but_not_counts() {
+        let candidates = rendered("2\n10 -6\n");
+        assert!(candidates.contains(&"2\n5 -6\n".to_string()));
+        assert!(candidates.contains(&"2\n10 -5\n".to_string()));
+        assert!(candidates.contains(&"2\n0 -6\n".to_string()));
+        assert!(!candidates.contains(&"1\n10 -6\n".to_string()));
+    }
+
+    #[test]
+    fn shrinks_to_a_valid_input() {
+        let input = b"1\n-1\n";
+        let shrunk = shrink(input, |input| Ok(input.windows(2).any(|w| w == b"-1"))).unwrap();
+        assert_eq!(shrunk, input);
+        let input = b"5\n4 9 -3 7 1\n";
+        let shrunk = shrink(input, |input| Ok(input.windows(2).any(|w| w == b"-3"))).unwrap();
+        assert_eq!(shrunk, b"1\n-3\n");
+    }
+}
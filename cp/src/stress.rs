@@ -0,0 +1,175 @@
+use crate::{
+    exec::{self, Execution, Limits, Program},
+    shrink,
+    testcase::{TestCase, WRONG_OUTPUT_EXTENSION},
+    threadpool::ThreadPool,
+    verdict::{Judge, Judgement, Verdict},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc},
+};
+
+/// Runs a generator, a brute force and a solution on increasing seeds until
+/// the solution disagrees with the brute force.
+pub struct Stress {
+    /// Called as `generator <seed>`, prints a test on stdout.
+    pub generator: Program,
+    pub brute: Program,
+    pub solution: Program,
+    /// Judges the solution, taking the output of the brute force as the answer.
+    pub judge: Judge,
+}
+
+/// A seed on which the solution was not accepted.
+pub struct Failure {
+    pub seed: u64,
+    pub input: Vec<u8>,
+    pub expected: Vec<u8>,
+    pub found: Vec<u8>,
+    pub judgement: Judgement,
+}
+
+impl Stress {
+    /// Tries the seeds from `first` on, `n_threads` at a time, until one of them
+    /// fails or `count` of them passed. `progress` gets the seeds tried so far.
+    pub fn run<F: FnMut(u64)>(
+        self: &Arc<Self>,
+        first: u64,
+        count: Option<u64>,
+        n_threads: usize,
+        mut progress: F,
+    ) -> io::Result<Option<Failure>> {
+        // At least one seed at a time, or none would ever run
+        let n_threads = n_threads.max(1);
+        let pool = ThreadPool::new(n_threads);
+        let mut seed = first;
+        loop {
+            let batch = match count {
+                Some(count) => (first + count - seed).min(n_threads as u64),
+                None => n_threads as u64,
+            };
+            if batch == 0 {
+                return Ok(None);
+            }
+            let (sender, receiver) = mpsc::channel();
+            for seed in seed..seed + batch {
+                let stress = Arc::clone(self);
+                let sender = sender.clone();
+                pool.execute(move || {
+                    sender.send((seed, stress.check(seed))).unwrap();
+                });
+            }
+            drop(sender);
+            let mut results: Vec<_> = receiver.iter().collect();
+            results.sort_unstable_by_key(|(seed, _)| *seed);
+            // The lowest failing seed, so that parallel runs stop on the same one
+            for (_, result) in results {
+                if let Some(failure) = result? {
+                    return Ok(Some(failure));
+                }
+            }
+            seed += batch;
+            progress(seed - first);
+        }
+    }
+
+    /// Runs a single seed, returning the failure if there is one.
+    pub fn check(&self, seed: u64) -> io::Result<Option<Failure>> {
+        let input = self.generate(seed)?;
+        self.check_input(seed, input)
+    }
+
+    /// Runs the brute force and the solution on `input`, generated from `seed`.
+    pub fn check_input(&self, seed: u64, input: Vec<u8>) -> io::Result<Option<Failure>> {
+        let expected = run_helper(&self.brute, &input, "brute force", seed)?.stdout;
+
+        let input_file = exec::temp_file("in");
+        let answer_file = exec::temp_file("ans");
+        fs::write(&input_file, &input)?;
+        fs::write(&answer_file, &expected)?;
+        let test = TestCase {
+            name: format!("seed {seed}"),
+            input: input_file,
+            answer: Some(answer_file),
+        };
+        let result = self.judge.run(&self.solution, &test);
+        fs::remove_file(&test.input)?;
+        fs::remove_file(test.answer.as_ref().unwrap())?;
+        let (execution, judgement) = result?;
+
+        if judgement.verdict == Verdict::Accepted {
+            return Ok(None);
+        }
+        Ok(Some(Failure {
+            seed,
+            input,
+            expected,
+            found: execution.stdout,
+            judgement,
+        }))
+    }
+
+    /// Shrinks the input of `failure` while the solution keeps failing on it
+    /// with the same verdict. Inputs on which the brute force fails are skipped,
+    /// as they are most likely not valid.
+    pub fn shrink(&self, failure: &Failure) -> io::Result<Failure> {
+        let verdict = failure.judgement.verdict;
+        let input = shrink::shrink(&failure.input, |candidate| {
+            Ok(match self.check_input(failure.seed, candidate.to_vec()) {
+                Ok(Some(found)) => found.judgement.verdict == verdict,
+                Ok(None) | Err(_) => false,
+            })
+        })?;
+        // Ran once more to get its answer and output back
+        self.check_input(failure.seed, input)?
+            .ok_or_else(|| io::Error::other("The shrunk input does not fail anymore"))
+    }
+
+    pub fn generate(&self, seed: u64) -> io::Result<Vec<u8>> {
+        let mut command = exec::command(&self.generator, &Limits::default());
+        command.arg(seed.to_string());
+        let execution = exec::run_command(command, &[], &Limits::default())?;
+        check_helper(execution, "generator", seed).map(|execution| execution.stdout)
+    }
+}
+
+impl Failure {
+    /// Saves the input as `stress-<seed>.in` in `dir`, the output of the brute
+    /// force as its answer and the one of the solution next to them.
+    pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let input = dir.join(format!("stress-{}.in", self.seed));
+        self.save_as(&input)?;
+        Ok(input)
+    }
+
+    /// Saves the shrunk input as `stress-<seed>.min.in` in `dir`, like `save`.
+    pub fn save_shrunk(&self, dir: &Path) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let input = dir.join(format!("stress-{}.min.in", self.seed));
+        self.save_as(&input)?;
+        Ok(input)
+    }
+
+    fn save_as(&self, input: &Path) -> io::Result<()> {
+        fs::write(input, &self.input)?;
+        fs::write(input.with_extension("ans"), &self.expected)?;
+        fs::write(input.with_extension(WRONG_OUTPUT_EXTENSION), &self.found)
+    }
+}
+
+fn run_helper(program: &Program, input: &[u8], name: &str, seed: u64) -> io::Result<Execution> {
+    let execution = exec::run(program, input, &Limits::default())?;
+    check_helper(execution, name, seed)
+}
+
+fn check_helper(execution: Execution, name: &str, seed: u64) -> io::Result<Execution> {
+    match execution.runtime_error() {
+        Some(error) => Err(io::Error::other(format!(
+            "The {name} failed on seed {seed}: {error}"
+        ))),
+        None => Ok(execution),
+    }
+}
@@ -0,0 +1,511 @@
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Extension of the wrong outputs saved next to the tests, like the ones of
+/// failed stress tests.
+pub static WRONG_OUTPUT_EXTENSION: &str = "got";
+
+/// How tests are found: the folders holding them and how inputs and their
+/// expected answers are named.
+#[derive(Clone)]
+pub struct Naming {
+    pub patterns: Vec<Pattern>,
+    /// Folders searched recursively, matched by their lowercase name. Only the
+    /// files right in the current directory are tests otherwise.
+    pub folders: Vec<Glob>,
+}
+
+impl Default for Naming {
+    fn default() -> Self {
+        let glob = |pattern: &str| pattern.parse().unwrap();
+        let pattern = |input: &str, answers: &[&str]| Pattern {
+            input: glob(input),
+            answers: answers.iter().map(|answer| glob(answer)).collect(),
+        };
+        Naming {
+            patterns: vec![
+                pattern("input*.txt", &["output*.txt"]),
+                pattern("*.in", &["*.ans", "*.out"]),
+                // Polygon
+                pattern("{n}", &["{n}.a"]),
+            ],
+            folders: vec![glob("input*"), glob("test*"), glob("sample*")],
+        }
+    }
+}
+
+/// The name of the inputs and the ones of their answers, filled with what the
+/// wildcards of the input matched.
+#[derive(Clone)]
+pub struct Pattern {
+    pub input: Glob,
+    pub answers: Vec<Glob>,
+}
+
+/// A pattern for file names: `*` matches anything, `?` any character and `{n}`
+/// a number. It works on the bytes of the names, which need not be UTF-8.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    Any,
+    One,
+    Number,
+}
+
+impl FromStr for Glob {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = Vec::new();
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            if let Some(after) = rest.strip_prefix("{n}") {
+                tokens.push(Token::Number);
+                rest = after;
+                continue;
+            }
+            tokens.push(match c {
+                '*' => Token::Any,
+                '?' => Token::One,
+                '{' | '}' => {
+                    return Err(format!("invalid pattern `{s}`, only `{{n}}` is supported"))
+                }
+                c => Token::Char(c),
+            });
+            rest = &rest[c.len_utf8()..];
+        }
+        if tokens.is_empty() {
+            return Err("empty pattern".to_string());
+        }
+        Ok(Glob { tokens })
+    }
+}
+
+impl Glob {
+    /// What every wildcard matched, if `name` matches.
+    pub fn captures(&self, name: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let mut captures = Vec::new();
+        capture(&self.tokens, name, &mut captures).then_some(captures)
+    }
+
+    pub fn matches(&self, name: &[u8]) -> bool {
+        self.captures(name).is_some()
+    }
+
+    /// The name with the wildcards replaced by `captures`, in order.
+    pub fn fill(&self, captures: &[Vec<u8>]) -> Vec<u8> {
+        let mut captures = captures.iter();
+        let mut name = Vec::new();
+        for token in &self.tokens {
+            match token {
+                Token::Char(c) => name.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+                _ => name.extend_from_slice(captures.next().map_or(&[], Vec::as_slice)),
+            }
+        }
+        name
+    }
+}
+
+fn capture(tokens: &[Token], name: &[u8], captures: &mut Vec<Vec<u8>>) -> bool {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return name.is_empty(),
+    };
+    let lengths = match token {
+        Token::Char(c) => {
+            let c = c.encode_utf8(&mut [0; 4]).as_bytes().to_vec();
+            return name.starts_with(&c) && capture(rest, &name[c.len()..], captures);
+        }
+        Token::One => match name.first() {
+            Some(&first) => {
+                let len = char_len(first).min(name.len());
+                len..=len
+            }
+            None => return false,
+        },
+        Token::Any => 0..=name.len(),
+        Token::Number => 1..=name.iter().take_while(|c| c.is_ascii_digit()).count(),
+    };
+    for len in lengths {
+        captures.push(name[..len].to_vec());
+        if capture(rest, &name[len..], captures) {
+            return true;
+        }
+        captures.pop();
+    }
+    false
+}
+
+/// The length of the UTF-8 character starting with `first`, 1 for a byte that
+/// doesn't start one.
+fn char_len(first: u8) -> usize {
+    match first.leading_ones() {
+        2..=4 => first.leading_ones() as usize,
+        _ => 1,
+    }
+}
+
+pub struct TestCase {
+    /// The path of the input in the folder the tests were found in:
+    /// `tests/a/1.in`.
+    pub name: String,
+    pub input: PathBuf,
+    pub answer: Option<PathBuf>,
+}
+
+/// The tests in `dir` and in its test folders, with their expected answers, in
+/// natural order. Files that are the answer of another test are not tests.
+pub fn discover(dir: &Path, naming: &Naming) -> io::Result<Vec<TestCase>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    for folder in folders(dir, &naming.folders)? {
+        walk(&folder, &mut files)?;
+    }
+    let mut tests: Vec<_> = files
+        .into_iter()
+        .filter_map(|file| test_case(dir, file, naming))
+        .collect();
+    let answers: HashSet<_> = tests
+        .iter()
+        .filter_map(|test| test.answer.clone())
+        .collect();
+    tests.retain(|test| !answers.contains(&test.input));
+    tests.sort_by(|a, b| {
+        natural_cmp(
+            &os_bytes(a.input.as_os_str()),
+            &os_bytes(b.input.as_os_str()),
+        )
+    });
+    Ok(tests)
+}
+
+/// The test folders in `dir`, sorted.
+pub fn folders(dir: &Path, globs: &[Glob]) -> io::Result<Vec<PathBuf>> {
+    let mut folders = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() && is_folder(&path, globs) {
+            folders.push(path);
+        }
+    }
+    folders.sort();
+    Ok(folders)
+}
+
+fn is_folder(path: &Path, globs: &[Glob]) -> bool {
+    let name = file_name(path).to_ascii_lowercase();
+    globs.iter().any(|glob| glob.matches(&name))
+}
+
+/// Whether `path`, right in the folder tests are discovered in, is a test
+/// folder or a file named like an input or an answer.
+pub fn is_test_path(path: &Path, naming: &Naming) -> bool {
+    if path.is_dir() {
+        return is_folder(path, &naming.folders);
+    }
+    let name = file_name(path);
+    naming.patterns.iter().any(|pattern| {
+        pattern.input.matches(&name) || pattern.answers.iter().any(|a| a.matches(&name))
+    })
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The test of `input`, found in `dir`, with its expected answer if there is
+/// one, unless it is not named like an input: `input3.txt` -> `output3.txt`,
+/// `3.in` -> `3.ans` or `3.out`.
+pub fn test_case(dir: &Path, input: PathBuf, naming: &Naming) -> Option<TestCase> {
+    let name = file_name(&input).into_owned();
+    let (pattern, captures) = naming
+        .patterns
+        .iter()
+        .find_map(|pattern| Some((pattern, pattern.input.captures(&name)?)))?;
+    let answer = pattern
+        .answers
+        .iter()
+        .map(|answer| input.with_file_name(os_string(answer.fill(&captures))))
+        .find(|answer| *answer != input && answer.is_file());
+    let name = input.strip_prefix(dir).unwrap_or(&input).to_string_lossy();
+    Some(TestCase {
+        name: name.into_owned(),
+        input,
+        answer,
+    })
+}
+
+/// Which of the discovered tests to run: `--only`, `--skip` and `--sample`.
+#[derive(Clone, Default)]
+pub struct Selection {
+    /// Any of them, all the tests if empty.
+    pub only: Vec<Filter>,
+    pub skip: Vec<Filter>,
+    /// How many tests to pick at random and the seed picking them.
+    pub sample: Option<(usize, u64)>,
+}
+
+/// Tests by name, or by their 1-based position in natural order: `3`, `3-7` or
+/// `3-`.
+#[derive(Clone)]
+pub enum Filter {
+    Name(Glob),
+    Index(RangeInclusive<usize>),
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let index = |n: &str| n.parse::<usize>().ok().filter(|&n| n > 0);
+        if let Some(n) = index(s) {
+            return Ok(Filter::Index(n..=n));
+        }
+        if let Some((start, end)) = s.split_once('-') {
+            let end = if end.is_empty() {
+                Some(usize::MAX)
+            } else {
+                index(end)
+            };
+            if let (Some(start), Some(end)) = (index(start), end) {
+                return Ok(Filter::Index(start..=end));
+            }
+        }
+        s.parse().map(Filter::Name)
+    }
+}
+
+impl Filter {
+    fn matches(&self, index: usize, test: &TestCase) -> bool {
+        match self {
+            Filter::Name(glob) => {
+                glob.matches(test.name.as_bytes()) || glob.matches(&file_name(&test.input))
+            }
+            Filter::Index(range) => range.contains(&(index + 1)),
+        }
+    }
+}
+
+impl Selection {
+    /// The selected tests, still in natural order.
+    pub fn apply(&self, tests: Vec<TestCase>) -> Vec<TestCase> {
+        let mut tests: Vec<_> = tests
+            .into_iter()
+            .enumerate()
+            .filter(|(i, test)| {
+                (self.only.is_empty() || self.only.iter().any(|f| f.matches(*i, test)))
+                    && !self.skip.iter().any(|f| f.matches(*i, test))
+            })
+            .map(|(_, test)| test)
+            .collect();
+        if let Some((n, seed)) = self.sample {
+            let mut rng = SplitMix64(seed);
+            let mut picked: Vec<usize> = (0..tests.len()).collect();
+            let n = n.min(picked.len());
+            // Partial Fisher-Yates: the first `n` are a uniform sample
+            for i in 0..n {
+                let j = i + (rng.next() % (picked.len() - i) as u64) as usize;
+                picked.swap(i, j);
+            }
+            let mut keep = vec![false; tests.len()];
+            for &i in &picked[..n] {
+                keep[i] = true;
+            }
+            let mut keep = keep.into_iter();
+            tests.retain(|_| keep.next().unwrap());
+        }
+        tests
+    }
+}
+
+/// A small generator, good enough to pick tests and the same on every platform
+/// for a given seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+fn file_name(path: &Path) -> Cow<'_, [u8]> {
+    os_bytes(path.file_name().unwrap_or_default())
+}
+
+/// The bytes of an OS string, as they are on Unix. Elsewhere the characters
+/// that are not Unicode are replaced.
+#[cfg(unix)]
+pub fn os_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn os_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    match s.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+/// The inverse of `os_bytes`.
+#[cfg(unix)]
+pub fn os_string(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+pub fn os_string(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+/// Compares numbers by value, so that `9.in` comes before `10.in`.
+pub fn natural_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let (x, y) = match (a.first(), b.first()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) => (x, y),
+        };
+        if x.is_ascii_digit() && y.is_ascii_digit() {
+            let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+            let (a_number, b_number) = (&a[..a_len], &b[..b_len]);
+            let a_digits = &a_number[a_number.iter().take_while(|&&c| c == b'0').count()..];
+            let b_digits = &b_number[b_number.iter().take_while(|&&c| c == b'0').count()..];
+            let order = a_digits
+                .len()
+                .cmp(&b_digits.len())
+                .then_with(|| a_digits.cmp(b_digits))
+                .then_with(|| a_number.len().cmp(&b_number.len()));
+            if order != Ordering::Equal {
+                return order;
+            }
+            a = &a[a_len..];
+            b = &b[b_len..];
+        } else {
+            if x != y {
+                return x.cmp(y);
+            }
+            a = &a[1..];
+            b = &b[1..];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures(pattern: &str, name: &str) -> Option<Vec<String>> {
+        let glob: Glob = pattern.parse().unwrap();
+        let captures = glob.captures(name.as_bytes())?;
+        Some(
+            captures
+                .into_iter()
+                .map(|capture| String::from_utf8(capture).unwrap())
+                .collect(),
+        )
+    }
+
+    fn fill(pattern: &str, captures: &[&str]) -> String {
+        let glob: Glob = pattern.parse().unwrap();
+        let captures: Vec<_> = captures.iter().map(|c| c.as_bytes().to_vec()).collect();
+        String::from_utf8(glob.fill(&captures)).unwrap()
+    }
+
+    #[test]
+    fn globs_backtrack() {
+        assert_eq!(captures("*.in", "a.b.in"), Some(vec!["a.b".into()]));
+        assert_eq!(captures("*.in", ".in"), Some(vec!["".into()]));
+        assert_eq!(captures("*.in", "a.in.ans"), None);
+        assert_eq!(captures("input*.txt", "input3.txt"), Some(vec!["3".into()]));
+        assert_eq!(
+            captures("*-*", "a-b-c"),
+            Some(vec!["a".into(), "b-c".into()])
+        );
+        // A number is only digits, and not empty
+        assert_eq!(captures("{n}", "042"), Some(vec!["042".into()]));
+        assert_eq!(captures("{n}", "4a"), None);
+        assert_eq!(captures("{n}", ""), None);
+        assert_eq!(captures("{n}.a", "12.a"), Some(vec!["12".into()]));
+        assert_eq!(captures("{n}1", "111"), Some(vec!["11".into()]));
+        assert_eq!(captures("{n}*", "12x"), Some(vec!["1".into(), "2x".into()]));
+    }
+
+    #[test]
+    fn question_marks_are_characters() {
+        assert_eq!(captures("?.in", "é.in"), Some(vec!["é".into()]));
+        assert_eq!(captures("??.in", "é.in"), None);
+        assert_eq!(captures("a?c", "a😀c"), Some(vec!["😀".into()]));
+        // Bytes that are not UTF-8 are one each
+        let glob: Glob = "?.in".parse().unwrap();
+        assert_eq!(glob.captures(b"\xff.in"), Some(vec![vec![0xff]]));
+        assert!(!glob.matches(b"\xff\xfe.in"));
+    }
+
+    #[test]
+    fn globs_are_filled_with_captures() {
+        assert_eq!(fill("*.ans", &["3"]), "3.ans");
+        assert_eq!(fill("output*.txt", &["07"]), "output07.txt");
+        assert_eq!(fill("{n}.a", &["12"]), "12.a");
+        assert_eq!(fill("é-*-?", &["a", "ü"]), "é-a-ü");
+        // Missing captures are empty
+        assert_eq!(fill("*-*", &["a"]), "a-");
+    }
+
+    #[test]
+    fn invalid_globs() {
+        assert!("".parse::<Glob>().is_err());
+        assert!("{m}".parse::<Glob>().is_err());
+        assert!("a}".parse::<Glob>().is_err());
+    }
+
+    #[test]
+    fn natural_order() {
+        let mut names = vec!["10.in", "9.in", "a.in", "1.in", "01.in", "b2", "b10", "b"];
+        names.sort_by(|a, b| natural_cmp(a.as_bytes(), b.as_bytes()));
+        assert_eq!(
+            names,
+            ["1.in", "01.in", "9.in", "10.in", "a.in", "b", "b2", "b10"]
+        );
+        assert_eq!(natural_cmp(b"007", b"7"), Ordering::Greater);
+        assert_eq!(natural_cmp(b"x007y", b"x7z"), Ordering::Greater);
+        assert_eq!(natural_cmp(b"x07z", b"x7y"), Ordering::Greater);
+        assert_eq!(natural_cmp(b"100", b"99"), Ordering::Greater);
+        assert_eq!(natural_cmp(b"input/2", b"input/2"), Ordering::Equal);
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     sync::{mpsc, Arc, Mutex},
     thread,
 };
@@ -47,6 +48,56 @@ impl ThreadPool {
         let job = Box::new(f);
         self.sender.as_ref().unwrap().send(job).unwrap();
     }
+
+    /// Runs `f` on every item, sending back `(index, result)` as soon as each
+    /// one is done, so in completion order.
+    pub fn map<T, R, F>(&self, items: Vec<T>, f: F) -> mpsc::Receiver<(usize, R)>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let (sender, receiver) = mpsc::channel();
+        for (i, item) in items.into_iter().enumerate() {
+            let f = Arc::clone(&f);
+            let sender = sender.clone();
+            self.execute(move || {
+                // The receiver may be gone after an error, nothing to report to
+                let _ = sender.send((i, f(item)));
+            });
+        }
+        receiver
+    }
+}
+
+/// Puts the results of `ThreadPool::map` back in the order of their indices.
+pub struct InOrder<R> {
+    next: usize,
+    pending: BTreeMap<usize, R>,
+}
+
+impl<R> Default for InOrder<R> {
+    fn default() -> Self {
+        InOrder {
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+impl<R> InOrder<R> {
+    /// Takes the result of index `i` and returns the ones that can now follow
+    /// those returned so far.
+    pub fn push(&mut self, i: usize, result: R) -> Vec<R> {
+        self.pending.insert(i, result);
+        let mut ready = Vec::new();
+        while let Some(result) = self.pending.remove(&self.next) {
+            ready.push(result);
+            self.next += 1;
+        }
+        ready
+    }
 }
 
 impl Drop for ThreadPool {
@@ -0,0 +1,228 @@
+use crate::{
+    checker::Checker,
+    compare,
+    exec::{self, Execution, Limits, Program},
+    interactor::Interactor,
+    testcase::TestCase,
+};
+use std::{fmt, fs, io};
+
+static ALLOCATION_FAILURES: [&str; 2] = ["memory allocation of", "std::bad_alloc"];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    Accepted,
+    WrongAnswer,
+    TimeLimitExceeded,
+    MemoryLimitExceeded,
+    RuntimeError,
+    PresentationError,
+    Partial,
+    CheckerFailed,
+    NoReference,
+}
+
+impl Verdict {
+    pub fn is_failure(self) -> bool {
+        !matches!(self, Verdict::Accepted | Verdict::NoReference)
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Accepted => write!(f, "AC"),
+            Verdict::WrongAnswer => write!(f, "WA"),
+            Verdict::TimeLimitExceeded => write!(f, "TLE"),
+            Verdict::MemoryLimitExceeded => write!(f, "MLE"),
+            Verdict::RuntimeError => write!(f, "RE"),
+            Verdict::PresentationError => write!(f, "PE"),
+            Verdict::Partial => write!(f, "PC"),
+            Verdict::CheckerFailed => write!(f, "FAIL"),
+            Verdict::NoReference => write!(f, "no reference"),
+        }
+    }
+}
+
+impl std::str::FromStr for Verdict {
+    type Err = String;
+
+    /// The inverse of `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "AC" => Verdict::Accepted,
+            "WA" => Verdict::WrongAnswer,
+            "TLE" => Verdict::TimeLimitExceeded,
+            "MLE" => Verdict::MemoryLimitExceeded,
+            "RE" => Verdict::RuntimeError,
+            "PE" => Verdict::PresentationError,
+            "PC" => Verdict::Partial,
+            "FAIL" => Verdict::CheckerFailed,
+            "no reference" => Verdict::NoReference,
+            _ => return Err(format!("unknown verdict `{s}`")),
+        })
+    }
+}
+
+/// A verdict together with what the checker or the runtime had to say about it.
+pub struct Judgement {
+    pub verdict: Verdict,
+    pub points: Option<f64>,
+    pub comment: Option<String>,
+}
+
+impl From<Verdict> for Judgement {
+    fn from(verdict: Verdict) -> Self {
+        Judgement {
+            verdict,
+            points: None,
+            comment: None,
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.verdict)?;
+        if let Some(points) = self.points {
+            write!(f, " {}", points)?;
+        }
+        if let Some(comment) = &self.comment {
+            write!(f, " ({})", comment)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct Summary {
+    results: Vec<(String, Verdict)>,
+}
+
+impl Summary {
+    pub fn add(&mut self, name: String, verdict: Verdict) {
+        self.results.push((name, verdict));
+    }
+
+    pub fn headline(&self) -> String {
+        let passed = self.count(|v| v == Verdict::Accepted);
+        let no_reference = self.count(|v| v == Verdict::NoReference);
+        let judged = self.results.len() - no_reference;
+        let mut line = format!("{passed}/{judged} passed");
+        if no_reference > 0 {
+            line.push_str(&format!(", {no_reference} without reference"));
+        }
+        line
+    }
+
+    /// Failed tests, in the order they were added.
+    pub fn failures(&self) -> Vec<&(String, Verdict)> {
+        self.results
+            .iter()
+            .filter(|(_, v)| v.is_failure())
+            .collect()
+    }
+
+    fn count<F: Fn(Verdict) -> bool>(&self, f: F) -> usize {
+        self.results.iter().filter(|(_, v)| f(*v)).count()
+    }
+}
+
+/// Everything needed to turn an execution into a verdict.
+#[derive(Clone, Default)]
+pub struct Judge {
+    pub limits: Limits,
+    pub checker: Option<Checker>,
+    pub compare: compare::Mode,
+    pub interactor: Option<Interactor>,
+}
+
+impl Judge {
+    /// Runs `program` on a test, against the interactor when there is one.
+    pub fn run(&self, program: &Program, test: &TestCase) -> io::Result<(Execution, Judgement)> {
+        if let Some(interactor) = &self.interactor {
+            let (execution, interaction) = interactor.run(program, test, &self.limits)?;
+            let judgement = self.judge_interaction(&execution, interaction);
+            return Ok((execution, judgement));
+        }
+        let execution = exec::run_file(program, &test.input, &self.limits)?;
+        let judgement = self.judge(&execution, test)?;
+        Ok((execution, judgement))
+    }
+
+    /// Judges a finished execution against the expected answer, if there is
+    /// one, through the checker when given.
+    pub fn judge(&self, execution: &Execution, test: &TestCase) -> io::Result<Judgement> {
+        if let Some(judgement) = self.exceeded_limits(execution) {
+            return Ok(judgement);
+        }
+        if let Some(judgement) = runtime_error(execution) {
+            return Ok(judgement);
+        }
+        let answer = match &test.answer {
+            Some(answer) => answer,
+            None => return Ok(Verdict::NoReference.into()),
+        };
+        if let Some(checker) = &self.checker {
+            return checker.check(&test.input, &execution.stdout, answer);
+        }
+        let verdict = if self.compare.matches(&execution.stdout, &fs::read(answer)?) {
+            Verdict::Accepted
+        } else {
+            Verdict::WrongAnswer
+        };
+        Ok(verdict.into())
+    }
+
+    /// Judges a run against the interactor. Its verdict wins over a runtime
+    /// error, which is often just the solution noticing that it hung up.
+    pub fn judge_interaction(&self, execution: &Execution, interaction: Judgement) -> Judgement {
+        if let Some(judgement) = self.exceeded_limits(execution) {
+            return judgement;
+        }
+        if interaction.verdict == Verdict::Accepted {
+            if let Some(judgement) = runtime_error(execution) {
+                return judgement;
+            }
+        }
+        interaction
+    }
+
+    fn exceeded_limits(&self, execution: &Execution) -> Option<Judgement> {
+        if execution.timed_out {
+            return Some(Verdict::TimeLimitExceeded.into());
+        }
+        match self.limits.memory {
+            Some(memory) if exceeded_memory(execution, memory) => {
+                Some(Verdict::MemoryLimitExceeded.into())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn runtime_error(execution: &Execution) -> Option<Judgement> {
+    execution.runtime_error().map(|error| Judgement {
+        verdict: Verdict::RuntimeError,
+        points: None,
+        comment: Some(error),
+    })
+}
+
+/// Under the rlimit an allocation fails before the peak RSS can get over the
+/// limit, so a child that died complaining about an allocation counts as well.
+fn exceeded_memory(execution: &Execution, memory: u64) -> bool {
+    if execution.usage.is_some_and(|usage| usage.max_rss > memory) {
+        return true;
+    }
+    !execution.status.success()
+        && ALLOCATION_FAILURES
+            .iter()
+            .any(|message| contains(&execution.stderr, message.as_bytes()))
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
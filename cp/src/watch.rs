@@ -0,0 +1,100 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// Keeps the entries of a folder worth watching.
+type Filter = Box<dyn Fn(&Path) -> bool + Send>;
+
+/// Polls files and folders for changes: files modified, added or removed.
+pub struct Watcher {
+    /// With whether the folders are watched recursively.
+    paths: Vec<(PathBuf, bool)>,
+    /// Folders of which only some entries are watched.
+    filtered: Vec<(PathBuf, Filter)>,
+    snapshot: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Watcher {
+    /// Watches `paths`, files or folders. Only the files right in a folder are
+    /// watched, unless it is recursive.
+    pub fn new(paths: Vec<(PathBuf, bool)>) -> Self {
+        let mut watcher = Watcher {
+            paths,
+            filtered: Vec::new(),
+            snapshot: BTreeMap::new(),
+        };
+        watcher.snapshot = watcher.scan();
+        watcher
+    }
+
+    /// Also watches the entries right in `dir` that `filter` keeps, folders
+    /// recursively, including the ones added later.
+    pub fn filtered(
+        mut self,
+        dir: PathBuf,
+        filter: impl Fn(&Path) -> bool + Send + 'static,
+    ) -> Self {
+        self.filtered.push((dir, Box::new(filter)));
+        self.snapshot = self.scan();
+        self
+    }
+
+    /// Blocks until something changed since the last call, or since the
+    /// watcher was created, checking every `interval`.
+    pub fn wait(&mut self, interval: Duration) {
+        loop {
+            thread::sleep(interval);
+            let snapshot = self.scan();
+            if snapshot != self.snapshot {
+                // Editors often write a file in several steps, let them finish
+                thread::sleep(interval);
+                self.snapshot = self.scan();
+                return;
+            }
+        }
+    }
+
+    fn scan(&self) -> BTreeMap<PathBuf, (Option<SystemTime>, u64)> {
+        let mut snapshot = BTreeMap::new();
+        for (path, recursive) in &self.paths {
+            scan(path, *recursive, true, &mut snapshot);
+        }
+        for (dir, filter) in &self.filtered {
+            for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                if filter(&path) {
+                    scan(&path, true, true, &mut snapshot);
+                }
+            }
+        }
+        snapshot
+    }
+}
+
+/// Adds the files of `path` to `snapshot`. Whatever can't be read now may
+/// appear later, and is seen then.
+fn scan(
+    path: &Path,
+    recursive: bool,
+    top: bool,
+    snapshot: &mut BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+) {
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(_) => return,
+    };
+    if metadata.is_file() {
+        snapshot.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+    } else if metadata.is_dir() && (top || recursive) {
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            scan(&entry.path(), recursive, false, snapshot);
+        }
+    }
+}